@@ -12,6 +12,7 @@ use iced::{Application, Element};
 use iced::{Color, Command, Font, Length, Settings, Subscription, time};
 
 use BlueToothCommand::bluetooth;
+use BlueToothCommand::locale::{self, tr, Phrase};
 
 pub fn main() -> iced::Result {
     EucInfo::run(Settings::default())
@@ -28,6 +29,7 @@ enum Message {
     Connected(Option<bluetooth::Device>),
     UpdatedDevice(bluetooth::Device),
     EucCommand(EucCommand),
+    SwitchLanguage,
     Tick,
 }
 
@@ -93,6 +95,7 @@ impl Application for EucInfo {
             Message::EucCommand(cmd) => if let Some(ref d) = self.device {
                 return Command::perform(Self::command(d.clone(), cmd), Message::UpdatedDevice);
             },
+            Message::SwitchLanguage => locale::set_language(locale::language().next()),
         };
         Command::none()
     }
@@ -100,21 +103,24 @@ impl Application for EucInfo {
     fn view(&self) -> Element<Message> {
         column![
             text( if let Some(d) = self.get_connect_device() {
-                    format!("Устройство: {name} -- Подключено\nSpeed: {info}"
+                    format!("{device}: {name} -- {connected}\n{info}"
+                        , device = tr(Phrase::Device)
                         , name = d.info.name
+                        , connected = tr(Phrase::Connected)
                         , info = &d.euc_info
                     )
-                } else {"Не подключено".into()}),
+                } else {tr(Phrase::NotConnected).into()}),
             if self.is_connected() {
                 row![
-                    button(text("Отключиться")).on_press(Message::Disconnect),
-                    button(text("Beep")).on_press(Message::EucCommand(EucCommand::Beep)),
-                    button(text("Led Turn")).on_press(Message::EucCommand(EucCommand::LedTurn)),
-                    button(text("Light Turn")).on_press(Message::EucCommand(EucCommand::LightTurn)),
+                    button(text(tr(Phrase::Disconnect))).on_press(Message::Disconnect),
+                    button(text(tr(Phrase::Beep))).on_press(Message::EucCommand(EucCommand::Beep)),
+                    button(text(tr(Phrase::LedTurn))).on_press(Message::EucCommand(EucCommand::LedTurn)),
+                    button(text(tr(Phrase::LightTurn))).on_press(Message::EucCommand(EucCommand::LightTurn)),
                 ].spacing(10)
             } else {
-                row![button(text("Подключиться")).on_press(Message::Connect(self.device_name()))]
+                row![button(text(tr(Phrase::Connect))).on_press(Message::Connect(self.device_name()))]
             },
+            button(text(tr(Phrase::SwitchLanguage))).on_press(Message::SwitchLanguage),
         ].spacing(20)
         .padding(50)
         .into()
@@ -135,8 +141,13 @@ impl EucInfo {
             }
             res.unwrap()
         };
-        let d = bluetooth::Device::new(p).await;
-        Some(d)
+        match bluetooth::Device::new(p).await {
+        Ok(d) => Some(d),
+        Err(err) => {
+            dbg!(err);
+            None
+        }
+        }
     }
 
     fn is_connected(&self) -> bool {
@@ -150,16 +161,24 @@ impl EucInfo {
         } else {None}
     }
     async fn update_device(mut d: bluetooth::Device) -> bluetooth::Device {
-        d.update_info().await;
+        if let Err(err) = d.update_info().await {
+            // shown as not connected until the next successful update
+            dbg!(err);
+            d.info.is_connected = false;
+        }
         d
     }
 
-    async fn command(device: bluetooth::Device, cmd: EucCommand) -> bluetooth::Device {
+    async fn command(mut device: bluetooth::Device, cmd: EucCommand) -> bluetooth::Device {
         let euc_info = device.euc_info.clone();
-        match cmd {
+        let sent = match cmd {
         EucCommand::Beep => device.beep().await,
         EucCommand::LedTurn => device.set_led_mode(euc_info.led_mode+1).await,
         EucCommand::LightTurn => device.set_light_mode(euc_info.light_mode+1).await,
+        };
+        if let Err(err) = sent {
+            dbg!(err);
         }
+        device
     }
 }
@@ -0,0 +1,26 @@
+// Plays a capture recorded with `Device::set_capture` and prints the decoded info.
+//
+//     cargo run --example replay -- ride.txt [speed factor, 0 for as fast as possible]
+
+use std::error::Error;
+
+use BlueToothCommand::bluetooth::{Device, ReplayTransport, ReplaySpeed};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut args = std::env::args().skip(1);
+    let path = args.next().ok_or("usage: replay <capture> [speed factor]")?;
+    let speed = match args.next().map(|s| s.parse::<f32>()).transpose()? {
+        None => ReplaySpeed::RealTime,
+        Some(factor) if factor > 0.0 => ReplaySpeed::Accelerated(factor),
+        Some(_) => ReplaySpeed::AsFastAsPossible,
+    };
+
+    let device = Device::with_transport(ReplayTransport::open(&path, speed)?).await?;
+    let mut info = device.subscribe();
+    while info.changed().await.is_ok() {
+        println!("{}", *info.borrow());
+    }
+    println!("{:?}", device.decoder_stats());
+    Ok(())
+}
@@ -16,6 +16,7 @@ use std::collections::BTreeMap;
 
 use BlueToothCommand::bluetooth;
 use bluetooth::BlueToothInfo;
+use BlueToothCommand::locale::{tr, Phrase};
 
 pub fn main() -> iced::Result {
     Scan::run(Settings::default())
@@ -65,7 +66,7 @@ impl Application for Scan {
         self.list.iter().fold(Column::new(),
             |c, (_name, info)| c.push(text(format!("Name: {};\n  is_connected: {}", info.name, info.is_connected)))
         )
-        .push(button(text("Обновить")).on_press(Message::UpdateList))
+        .push(button(text(tr(Phrase::Refresh))).on_press(Message::UpdateList))
         .padding(20)
         .spacing(10)
 //         .align_items(Alignment::Center)
@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use crate::bluetooth::{EucInfo, Field};
+use crate::bluetooth::frame::{Alert, Alerts};
+use crate::trip::{TripId, SampleClock};
+
+/// Changes of the wheel's own alert bits, with `at` on the same clock as the samples.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlertEvent {
+    Started { alert: Alert, at: Duration },
+    Ended { alert: Alert, at: Duration, duration: Duration },
+}
+
+/// One alert from start to end, `end` is `None` while it lasts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertRecord {
+    pub alert: Alert,
+    pub trip: Option<TripId>,   // trip in progress when it started
+    pub start: Duration,
+    pub end: Option<Duration>,
+}
+
+impl AlertRecord {
+    pub fn duration(&self) -> Option<Duration> {
+        self.end.map(|end| end.saturating_sub(self.start))
+    }
+}
+
+/// Follows the [`Alerts`] of successive FrameBs and keeps the history of
+/// every alert the wheel raised.
+#[derive(Debug, Clone)]
+pub struct AlertTracker {
+    history: Vec<AlertRecord>,
+    open: Vec<usize>,   // indices in `history` of the alerts still on
+    last: Option<Duration>,
+    clock: SampleClock,
+}
+
+impl Default for AlertTracker {
+    fn default() -> Self {
+        Self {
+            history: Vec::new(),
+            open: Vec::new(),
+            last: None,
+            clock: SampleClock::of(Field::Alerts),
+        }
+    }
+}
+
+impl AlertTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the alerts of the latest telemetry info, timed by its last
+    /// FrameB; infos without a new FrameB are ignored.
+    pub fn push(&mut self, info: &EucInfo, trip: Option<TripId>) -> Vec<AlertEvent> {
+        match self.clock.at(info) {
+        Some(at) => self.update(&info.alerts, at, trip),
+        None => Vec::new(),
+        }
+    }
+
+    /// Compares `alerts` received `at` from any fixed origin with the
+    /// previous ones; alerts not newer than the previous ones are ignored.
+    pub fn update(&mut self, alerts: &Alerts, at: Duration, trip: Option<TripId>) -> Vec<AlertEvent> {
+        if matches!(self.last, Some(last) if at <= last) {
+            return Vec::new();
+        }
+        self.last = Some(at);
+        let mut events = self.end_where(at, |alert| !alerts.iter().any(|a| a == alert));
+        for alert in alerts.iter() {
+            if !self.open.iter().any(|&i| &self.history[i].alert == alert) {
+                self.open.push(self.history.len());
+                self.history.push(AlertRecord { alert: alert.clone(), trip, start: at, end: None });
+                events.push(AlertEvent::Started { alert: alert.clone(), at });
+            }
+        }
+        events
+    }
+
+    /// The link is gone: ends every alert still on at the last FrameB.
+    pub fn disconnect(&mut self) -> Vec<AlertEvent> {
+        let events = self.end_where(self.last.unwrap_or_default(), |_| true);
+        self.last = None;
+        self.clock.reset();
+        events
+    }
+
+    fn end_where(&mut self, at: Duration, ended: impl Fn(&Alert) -> bool) -> Vec<AlertEvent> {
+        let mut events = Vec::new();
+        let history = &mut self.history;
+        self.open.retain(|&i| {
+            let record = &mut history[i];
+            if !ended(&record.alert) {
+                return true;
+            }
+            record.end = Some(at);
+            events.push(AlertEvent::Ended { alert: record.alert.clone(), at, duration: at.saturating_sub(record.start) });
+            false
+        });
+        events
+    }
+
+    /// Alerts currently on.
+    pub fn active(&self) -> impl Iterator<Item = &Alert> {
+        self.open.iter().map(|&i| &self.history[i].alert)
+    }
+
+    /// Every alert seen, in start order.
+    pub fn history(&self) -> &[AlertRecord] {
+        &self.history
+    }
+
+    /// Alerts started during `trip`.
+    pub fn trip_history(&self, trip: TripId) -> impl Iterator<Item = &AlertRecord> {
+        self.history.iter().filter(move |record| record.trip == Some(trip))
+    }
+
+    /// Forgets every ended alert, e.g. once a trip has been saved.
+    pub fn clear_history(&mut self) {
+        let open: Vec<_> = self.open.iter().map(|&i| self.history[i].clone()).collect();
+        self.open = (0..open.len()).collect();
+        self.history = open;
+    }
+}
+
+#[test]
+fn test_alert_tracker() {
+    let ms = Duration::from_millis;
+    let mut tracker = AlertTracker::new();
+    let trip = Some(TripId(1));
+    // 0x20 OverTemperature, 0x04 Speed1
+    assert!(tracker.update(&Alerts::from(0x00), ms(0), trip).is_empty());
+    assert_eq!(tracker.update(&Alerts::from(0x20), ms(100), trip),
+        vec![AlertEvent::Started { alert: Alert::OverTemperature, at: ms(100) }]);
+    assert!(tracker.update(&Alerts::from(0x20), ms(200), trip).is_empty());
+    // the same FrameB again
+    assert!(tracker.update(&Alerts::from(0x00), ms(200), trip).is_empty());
+    assert_eq!(tracker.update(&Alerts::from(0x04), ms(300), trip), vec![
+        AlertEvent::Ended { alert: Alert::OverTemperature, at: ms(300), duration: ms(200) },
+        AlertEvent::Started { alert: Alert::Speed1, at: ms(300) },
+    ]);
+    assert_eq!(tracker.active().collect::<Vec<_>>(), vec![&Alert::Speed1]);
+
+    tracker.update(&Alerts::from(0x24), ms(400), Some(TripId(2)));
+    assert_eq!(tracker.disconnect().len(), 2);
+    assert_eq!(tracker.active().count(), 0);
+
+    assert_eq!(tracker.history().len(), 3);
+    assert_eq!(tracker.history()[0].duration(), Some(ms(200)));
+    assert_eq!(tracker.history()[1].duration(), Some(ms(100)));
+    assert_eq!(tracker.trip_history(TripId(1)).count(), 2);
+    let second: Vec<_> = tracker.trip_history(TripId(2)).collect();
+    assert_eq!((second[0].alert.clone(), second[0].start, second[0].end), (Alert::OverTemperature, ms(400), Some(ms(400))));
+    tracker.clear_history();
+    assert!(tracker.history().is_empty());
+}
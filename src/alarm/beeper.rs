@@ -0,0 +1,159 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use super::{AlarmEvent, AlarmId};
+use crate::bluetooth::WheelCommand;
+
+/// Beeps of the wheel's buzzer played for an alarm.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BeepPattern {
+    pub count: u8,
+    pub interval: Duration,     // between the starts of two beeps
+}
+
+impl BeepPattern {
+    /// Most beeps in one pattern.
+    pub const MAX_COUNT: u8 = 5;
+    /// Shortest interval, each beep is one write.
+    pub const MIN_INTERVAL: Duration = WheelCommand::PACING;
+
+    /// Pattern with `count` and `interval` brought into the limits above.
+    pub fn new(count: u8, interval: Duration) -> Self {
+        Self {
+            count: count.clamp(1, Self::MAX_COUNT),
+            interval: interval.max(Self::MIN_INTERVAL),
+        }
+    }
+
+    /// Time from the first beep to the end of the last interval.
+    pub fn duration(&self) -> Duration {
+        self.interval * self.count as u32
+    }
+}
+
+impl Default for BeepPattern {
+    fn default() -> Self {
+        Self::new(2, Duration::from_millis(300))
+    }
+}
+
+/// Decides when to sound the wheel's buzzer for the alarms of an
+/// [`super::AlarmEngine`].
+///
+/// A pattern plays when an alarm with a pattern is raised and, while it stays
+/// raised, again every `repeat` if set. Two patterns are always at least
+/// `min_gap` apart, so the link and the rider get some rest; a pattern due
+/// earlier waits for the gap. Play them with
+/// [`crate::bluetooth::Device::beep_pattern`].
+#[derive(Debug, Clone)]
+pub struct Beeper {
+    pub min_gap: Duration,
+    pub repeat: Option<Duration>,
+
+    patterns: HashMap<AlarmId, BeepPattern>,
+    active: HashSet<AlarmId>,
+    pending: Option<BeepPattern>,
+    last_played: Option<Duration>,
+}
+
+impl Default for Beeper {
+    fn default() -> Self {
+        Self {
+            min_gap: Duration::from_secs(10),
+            repeat: Some(Duration::from_secs(60)),
+            patterns: HashMap::new(),
+            active: HashSet::new(),
+            pending: None,
+            last_played: None,
+        }
+    }
+}
+
+impl Beeper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_min_gap(self, min_gap: Duration) -> Self {
+        Self { min_gap, ..self }
+    }
+
+    /// Repeat interval while an alarm stays raised, `None` to beep once.
+    pub fn with_repeat(self, repeat: Option<Duration>) -> Self {
+        Self { repeat, ..self }
+    }
+
+    /// Beeps `pattern` for `alarm`; alarms without a pattern are silent.
+    pub fn set_pattern(&mut self, alarm: AlarmId, pattern: Option<BeepPattern>) {
+        match pattern {
+        Some(pattern) => self.patterns.insert(alarm, pattern),
+        None => self.patterns.remove(&alarm),
+        };
+    }
+
+    /// Takes the alarm events of a sample taken `at` and returns the pattern
+    /// to play now, if any.
+    pub fn update(&mut self, events: &[AlarmEvent], at: Duration) -> Option<BeepPattern> {
+        for event in events {
+            match event {
+            AlarmEvent::Raised { alarm, .. } => if let Some(&pattern) = self.patterns.get(alarm) {
+                self.active.insert(*alarm);
+                self.pending = Some(self.pending.map_or(pattern, |p| Self::louder(p, pattern)));
+            }
+            AlarmEvent::Cleared { alarm, .. } => {
+                self.active.remove(alarm);
+            }
+            }
+        }
+        if self.active.is_empty() {
+            self.pending = None;
+            return None;
+        }
+        if let (Some(repeat), Some(last)) = (self.repeat, self.last_played) {
+            if self.pending.is_none() && at.saturating_sub(last) >= repeat {
+                self.pending = self.active.iter()
+                    .filter_map(|alarm| self.patterns.get(alarm).copied())
+                    .reduce(Self::louder);
+            }
+        }
+        if matches!(self.last_played, Some(last) if at.saturating_sub(last) < self.min_gap) {
+            return None;
+        }
+        let pattern = self.pending.take()?;
+        self.last_played = Some(at);
+        Some(pattern)
+    }
+
+    fn louder(a: BeepPattern, b: BeepPattern) -> BeepPattern {
+        if b.count > a.count {b} else {a}
+    }
+}
+
+#[test]
+fn test_beeper() {
+    use super::Measure;
+    let secs = Duration::from_secs;
+    let raised = |alarm| AlarmEvent::Raised { alarm, measure: Measure::BatteryPercent, value: 19.0, at: Duration::ZERO };
+    let cleared = |alarm| AlarmEvent::Cleared { alarm, measure: Measure::BatteryPercent, value: 21.0, at: Duration::ZERO, duration: Duration::ZERO };
+    let (battery, heat, silent) = (AlarmId(0), AlarmId(1), AlarmId(2));
+    let mut beeper = Beeper::new()
+        .with_min_gap(secs(10))
+        .with_repeat(Some(secs(30)));
+    beeper.set_pattern(battery, Some(BeepPattern::new(1, Duration::ZERO)));
+    beeper.set_pattern(heat, Some(BeepPattern::new(9, Duration::from_millis(200))));
+    assert_eq!(BeepPattern::new(9, Duration::ZERO), BeepPattern { count: 5, interval: BeepPattern::MIN_INTERVAL });
+
+    assert_eq!(beeper.update(&[raised(silent)], secs(0)), None);
+    assert_eq!(beeper.update(&[raised(battery)], secs(1)).map(|p| p.count), Some(1));
+    // rate limited, plays once the gap is over
+    assert_eq!(beeper.update(&[raised(heat)], secs(2)), None);
+    assert_eq!(beeper.update(&[], secs(5)), None);
+    assert_eq!(beeper.update(&[], secs(11)).map(|p| p.count), Some(5));
+    assert_eq!(beeper.update(&[], secs(20)), None);
+    // repeated while raised, the louder pattern wins
+    assert_eq!(beeper.update(&[], secs(41)).map(|p| p.count), Some(5));
+    assert_eq!(beeper.update(&[cleared(heat)], secs(50)), None);
+    assert_eq!(beeper.update(&[], secs(71)).map(|p| p.count), Some(1));
+    assert_eq!(beeper.update(&[cleared(battery)], secs(72)), None);
+    assert_eq!(beeper.update(&[], secs(200)), None);
+}
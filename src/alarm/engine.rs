@@ -0,0 +1,219 @@
+use std::time::Duration;
+
+use crate::bluetooth::EucInfo;
+use crate::trip::SampleClock;
+
+/// Value of [`EucInfo`] an alarm watches, always in metric units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Measure {
+    Speed,          // km/h, either direction
+    Current,        // A
+    Power,          // W
+    Temperature,    // Celsius degrees, board
+    MotorTemperature,
+    CellVoltage,    // V
+    BatteryPercent, // %
+    Pwm,            // %
+}
+
+impl Measure {
+    pub fn of(self, info: &EucInfo) -> f32 {
+        match self {
+        Measure::Speed => info.speed.kmh().abs(),
+        Measure::Current => info.current.0,
+        Measure::Power => info.power().0,
+        Measure::Temperature => info.temperature.celsius(),
+        Measure::MotorTemperature => info.motor_temperature.celsius(),
+        Measure::CellVoltage => info.cell_voltage.0,
+        Measure::BatteryPercent => info.battery_percent,
+        Measure::Pwm => info.pwm.abs(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Condition {
+    Above,
+    Below,
+}
+
+/// One user defined alarm.
+///
+/// It is raised once the measure has been past `threshold` for
+/// `min_duration`, and cleared once it has been back by more than
+/// `hysteresis` for `min_duration` as well.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlarmRule {
+    pub measure: Measure,
+    pub condition: Condition,
+    pub threshold: f32,
+    pub hysteresis: f32,
+    pub min_duration: Duration,
+}
+
+impl AlarmRule {
+    pub fn above(measure: Measure, threshold: f32) -> Self {
+        Self { measure, condition: Condition::Above, threshold, hysteresis: 0.0, min_duration: Duration::ZERO }
+    }
+
+    pub fn below(measure: Measure, threshold: f32) -> Self {
+        Self { condition: Condition::Below, ..Self::above(measure, threshold) }
+    }
+
+    pub fn with_hysteresis(self, hysteresis: f32) -> Self {
+        Self { hysteresis: hysteresis.abs(), ..self }
+    }
+
+    pub fn with_min_duration(self, min_duration: Duration) -> Self {
+        Self { min_duration, ..self }
+    }
+
+    fn tripped(&self, value: f32) -> bool {
+        match self.condition {
+        Condition::Above => value > self.threshold,
+        Condition::Below => value < self.threshold,
+        }
+    }
+
+    fn recovered(&self, value: f32) -> bool {
+        match self.condition {
+        Condition::Above => value <= self.threshold - self.hysteresis,
+        Condition::Below => value >= self.threshold + self.hysteresis,
+        }
+    }
+}
+
+/// Index of a rule in its [`AlarmEngine`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AlarmId(pub usize);
+
+/// Alarm transitions, with `at` on the same clock as the samples.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlarmEvent {
+    Raised { alarm: AlarmId, measure: Measure, value: f32, at: Duration },
+    Cleared { alarm: AlarmId, measure: Measure, value: f32, at: Duration, duration: Duration },
+}
+
+impl AlarmEvent {
+    pub fn alarm(&self) -> AlarmId {
+        match self {
+        AlarmEvent::Raised { alarm, .. } | AlarmEvent::Cleared { alarm, .. } => *alarm,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    /// `pending` since when the rule has been tripped.
+    Inactive { pending: Option<Duration> },
+    Active { since: Duration, pending: Option<Duration> },
+}
+
+/// Evaluates [`AlarmRule`]s against every [`EucInfo`] update.
+#[derive(Debug, Clone, Default)]
+pub struct AlarmEngine {
+    rules: Vec<(AlarmRule, State)>,
+    clock: SampleClock,
+}
+
+impl AlarmEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, rule: AlarmRule) -> AlarmId {
+        self.rules.push((rule, State::Inactive { pending: None }));
+        AlarmId(self.rules.len() - 1)
+    }
+
+    pub fn rule(&self, alarm: AlarmId) -> Option<&AlarmRule> {
+        self.rules.get(alarm.0).map(|(rule, _)| rule)
+    }
+
+    pub fn is_active(&self, alarm: AlarmId) -> bool {
+        matches!(self.rules.get(alarm.0), Some((_, State::Active { .. })))
+    }
+
+    /// Alarms currently raised.
+    pub fn active(&self) -> impl Iterator<Item = AlarmId> + '_ {
+        self.rules.iter().enumerate()
+            .filter(|(_, (_, state))| matches!(state, State::Active { .. }))
+            .map(|(i, _)| AlarmId(i))
+    }
+
+    /// Evaluates every rule on the latest telemetry info, timed by its last FrameA.
+    pub fn push(&mut self, info: &EucInfo) -> Vec<AlarmEvent> {
+        match self.clock.at(info) {
+        Some(at) => self.update(info, at),
+        None => Vec::new(),
+        }
+    }
+
+    /// Evaluates every rule on a sample taken `at` from any fixed origin.
+    pub fn update(&mut self, info: &EucInfo, at: Duration) -> Vec<AlarmEvent> {
+        let mut events = Vec::new();
+        for (i, (rule, state)) in self.rules.iter_mut().enumerate() {
+            let alarm = AlarmId(i);
+            let measure = rule.measure;
+            let value = measure.of(info);
+            let lasted = |pending: Option<Duration>| {
+                let since = pending.unwrap_or(at);
+                (since, at.saturating_sub(since) >= rule.min_duration)
+            };
+            *state = match *state {
+            State::Inactive { pending } if rule.tripped(value) => match lasted(pending) {
+                (_, true) => {
+                    events.push(AlarmEvent::Raised { alarm, measure, value, at });
+                    State::Active { since: at, pending: None }
+                }
+                (since, false) => State::Inactive { pending: Some(since) },
+                },
+            State::Inactive { .. } => State::Inactive { pending: None },
+            State::Active { since, pending } if rule.recovered(value) => match lasted(pending) {
+                (_, true) => {
+                    events.push(AlarmEvent::Cleared { alarm, measure, value, at, duration: at.saturating_sub(since) });
+                    State::Inactive { pending: None }
+                }
+                (cleared, false) => State::Active { since, pending: Some(cleared) },
+                },
+            State::Active { since, .. } => State::Active { since, pending: None },
+            };
+        }
+        events
+    }
+}
+
+#[test]
+fn test_alarm_engine() {
+    use crate::units::{Speed, Voltage};
+    let ms = Duration::from_millis;
+    let speed = |kmh| EucInfo { speed: Speed(kmh), cell_voltage: Voltage(3.8), ..Default::default() };
+    let mut engine = AlarmEngine::new();
+    let fast = engine.add(AlarmRule::above(Measure::Speed, 40.0)
+        .with_hysteresis(5.0)
+        .with_min_duration(ms(500)));
+    let low = engine.add(AlarmRule::below(Measure::CellVoltage, 3.3));
+
+    assert!(engine.update(&speed(45.0), ms(0)).is_empty());
+    // a spike shorter than the minimum duration
+    assert!(engine.update(&speed(30.0), ms(300)).is_empty());
+    assert!(engine.update(&speed(41.0), ms(400)).is_empty());
+    assert!(engine.update(&speed(42.0), ms(800)).is_empty());
+    assert_eq!(engine.update(&speed(-43.0), ms(900)),
+        vec![AlarmEvent::Raised { alarm: fast, measure: Measure::Speed, value: 43.0, at: ms(900) }]);
+    assert!(engine.is_active(fast));
+    // inside the hysteresis band
+    assert!(engine.update(&speed(38.0), ms(1000)).is_empty());
+    assert!(engine.update(&speed(34.0), ms(2000)).is_empty());
+    assert!(engine.update(&speed(36.0), ms(2100)).is_empty());
+    assert!(engine.update(&speed(30.0), ms(2200)).is_empty());
+    assert_eq!(engine.update(&speed(30.0), ms(2700)),
+        vec![AlarmEvent::Cleared { alarm: fast, measure: Measure::Speed, value: 30.0, at: ms(2700), duration: ms(1800) }]);
+    assert_eq!(engine.active().count(), 0);
+
+    let sagging = EucInfo { cell_voltage: Voltage(3.2), ..Default::default() };
+    let events = engine.update(&sagging, ms(3000));
+    assert_eq!(events.iter().map(AlarmEvent::alarm).collect::<Vec<_>>(), vec![low]);
+    assert_eq!(engine.active().collect::<Vec<_>>(), vec![low]);
+    assert_eq!(engine.rule(low).unwrap().condition, Condition::Below);
+}
@@ -0,0 +1,7 @@
+mod engine;
+mod alerts;
+mod beeper;
+
+pub use engine::{AlarmEngine, AlarmRule, AlarmId, AlarmEvent, Measure, Condition};
+pub use alerts::{AlertTracker, AlertEvent, AlertRecord};
+pub use beeper::{Beeper, BeepPattern};
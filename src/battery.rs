@@ -0,0 +1,192 @@
+use std::num::NonZeroU8;
+
+use crate::units::{Voltage, Current};
+
+/// Battery pack of the wheel.
+///
+/// GotWay/Begode boards report the voltage as if every wheel had a 16s
+/// (67.2V) pack, so the raw value has to be rescaled by `cells / 16`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatteryConfig {
+    pub cells: u8,          // series cell count
+    pub soc: SocModel,      // how the charge percentage is derived from the cell voltage
+    pub resistance: f32,    // pack internal resistance in ohms used for sag compensation, 0 disables it
+}
+
+impl BatteryConfig {
+    pub const V67: Self = Self::series(16);
+    pub const V84: Self = Self::series(20);
+    pub const V100: Self = Self::series(24);
+    pub const V126: Self = Self::series(30);
+    pub const V134: Self = Self::series(32);
+    pub const V151: Self = Self::series(36);
+    pub const V168: Self = Self::series(40);
+
+    const fn series(cells: u8) -> Self {
+        Self { cells, soc: SocModel::LINEAR, resistance: 0.0 }
+    }
+
+    /// Series cell count the raw frame voltage is based on.
+    const RAW_CELLS: u8 = 16;
+    const CELL_FULL: f32 = 4.2;
+
+    /// Pack of `cells` in series; a pack without cells would scale every voltage to 0.
+    pub fn from_cells(cells: NonZeroU8) -> Self {
+        Self::series(cells.get())
+    }
+
+    pub fn with_soc(self, soc: SocModel) -> Self {
+        Self { soc, ..self }
+    }
+
+    pub fn with_resistance(self, resistance: f32) -> Self {
+        Self { resistance, ..self }
+    }
+
+    /// Pack by its nominal name (67, 84, 100, 126, 134, 151 or 168 volts).
+    pub fn from_nominal(volts: u16) -> Option<Self> {
+        let config = match volts {
+        67 => Self::V67,
+        84 => Self::V84,
+        100 => Self::V100,
+        126 => Self::V126,
+        134 => Self::V134,
+        151 => Self::V151,
+        168 => Self::V168,
+        _ => return None,
+        };
+        Some(config)
+    }
+
+    /// Fully charged pack voltage.
+    pub fn full_voltage(&self) -> Voltage {
+        Voltage(self.cells as f32 * Self::CELL_FULL)
+    }
+
+    fn scale(&self) -> f32 {
+        self.cells as f32 / Self::RAW_CELLS as f32
+    }
+
+    /// Pack voltage from the voltage reported in FrameA.
+    pub fn pack_voltage(&self, raw: Voltage) -> Voltage {
+        Voltage(raw.0 * self.scale())
+    }
+
+    /// Voltage for FrameA from the real pack voltage.
+    pub fn raw_voltage(&self, pack: Voltage) -> Voltage {
+        Voltage(pack.0 / self.scale())
+    }
+
+    pub fn cell_voltage(&self, pack: Voltage) -> Voltage {
+        Voltage(pack.0 / self.cells as f32)
+    }
+
+    /// State of charge in percent from the pack voltage under `current` load.
+    ///
+    /// The voltage sag caused by the load is added back before looking up
+    /// the curve, so the percentage doesn't drop while accelerating.
+    pub fn percent(&self, pack: Voltage, current: Current) -> f32 {
+        let rest = Voltage(pack.0 + current.0 * self.resistance);
+        self.soc.percent(self.cell_voltage(rest))
+    }
+}
+
+/// Model mapping the resting cell voltage to a charge percentage.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SocModel {
+    /// Straight line between the empty and full cell voltages.
+    Linear { empty: f32, full: f32 },
+    /// The "better percents" curve of the WheelLog Android app:
+    /// steeper below 3.4V per cell, linear above it.
+    BetterPercent,
+    /// Custom `(cell voltage, percent)` points sorted by voltage,
+    /// interpolated linearly between points.
+    Table(Vec<(f32, f32)>),
+}
+
+impl SocModel {
+    /// WheelLog's default GotWay curve: 52.90V..65.80V on a 16s pack.
+    pub const LINEAR: Self = SocModel::Linear { empty: 52.90 / 16.0, full: 65.80 / 16.0 };
+
+    pub fn percent(&self, cell: Voltage) -> f32 {
+        let cell = cell.0;
+        let percent = match self {
+        SocModel::Linear { empty, full } => (cell - empty) / (full - empty) * 100.0,
+        SocModel::BetterPercent => {
+            // WheelLog thresholds are centivolts of a 16s pack
+            let v = cell * 16.0 * 100.0;
+            if v > 6680.0 {
+                100.0
+            } else if v > 5440.0 {
+                (v - 5380.0) / 13.0
+            } else if v > 5290.0 {
+                (v - 5290.0) / 32.5
+            } else {
+                0.0
+            }
+        }
+        SocModel::Table(points) => {
+            match points.iter().position(|&(v, _)| v > cell) {
+            None => points.last().map_or(0.0, |&(_, p)| p),
+            Some(0) => points[0].1,
+            Some(i) => {
+                let ((v0, p0), (v1, p1)) = (points[i-1], points[i]);
+                p0 + (p1 - p0) * (cell - v0) / (v1 - v0)
+            }
+            }
+        }
+        };
+        percent.clamp(0.0, 100.0)
+    }
+}
+
+impl Default for SocModel {
+    fn default() -> Self {
+        Self::LINEAR
+    }
+}
+
+impl Default for BatteryConfig {
+    fn default() -> Self {
+        Self::V67
+    }
+}
+
+#[test]
+fn test_battery_rescale() {
+    assert_eq!(BatteryConfig::from_nominal(100), Some(BatteryConfig::V100));
+    assert_eq!(BatteryConfig::from_nominal(99), None);
+    assert_eq!(BatteryConfig::V134.full_voltage(), Voltage(134.4));
+    assert_eq!(NonZeroU8::new(24).map(BatteryConfig::from_cells), Some(BatteryConfig::V100));
+    assert_eq!(NonZeroU8::new(0).map(BatteryConfig::from_cells), None);
+
+    let battery = BatteryConfig::V100;
+    let pack = battery.pack_voltage(Voltage(58.26));
+    assert!((pack.0 - 87.39).abs() < 1e-3);
+    assert!((battery.cell_voltage(pack).0 - 3.64125).abs() < 1e-4);
+    assert!((battery.raw_voltage(pack).0 - 58.26).abs() < 1e-4);
+    assert_eq!(BatteryConfig::default().pack_voltage(Voltage(58.26)), Voltage(58.26));
+}
+
+#[test]
+fn test_battery_percent() {
+    let linear = SocModel::default();
+    assert_eq!(linear.percent(Voltage(3.0)), 0.0);
+    assert_eq!(linear.percent(Voltage(4.2)), 100.0);
+    assert!((linear.percent(Voltage(59.35 / 16.0)) - 50.0).abs() < 1e-3);
+
+    let better = SocModel::BetterPercent;
+    assert_eq!(better.percent(Voltage(4.2)), 100.0);
+    assert!((better.percent(Voltage(54.40 / 16.0)) - 60.0 / 13.0).abs() < 1e-3);
+    assert!((better.percent(Voltage(60.30 / 16.0)) - 50.0).abs() < 1e-3);
+    assert_eq!(better.percent(Voltage(3.0)), 0.0);
+
+    let table = SocModel::Table(vec![(3.3, 0.0), (3.6, 20.0), (4.1, 100.0)]);
+    assert_eq!(table.percent(Voltage(3.2)), 0.0);
+    assert!((table.percent(Voltage(3.45)) - 10.0).abs() < 1e-3);
+    assert_eq!(table.percent(Voltage(4.15)), 100.0);
+
+    // 10A through 0.1 ohm sags the 84V pack by a volt
+    let battery = BatteryConfig::V84.with_resistance(0.1);
+    assert_eq!(battery.percent(Voltage(72.0), Current(10.0)), BatteryConfig::V84.percent(Voltage(73.0), Current(0.0)));
+}
@@ -0,0 +1,219 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// First line of every capture file.
+const MAGIC: &str = "# gotway capture v1";
+
+/// Which way a captured chunk went over the link.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    Inbound,    // notification from the wheel, `<`
+    Outbound,   // command to the wheel, `>`
+}
+
+impl Direction {
+    fn marker(self) -> char {
+        match self {
+        Direction::Inbound => '<',
+        Direction::Outbound => '>',
+        }
+    }
+}
+
+/// One captured chunk, exactly as it went over the link.
+///
+/// In a capture file it is one line: seconds since the capture started,
+/// direction marker and hex bytes, e.g. `12.345678 < 55aa16c2`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptureRecord {
+    pub at: Duration,
+    pub direction: Direction,
+    pub bytes: Vec<u8>,
+}
+
+impl std::fmt::Display for CaptureRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{:06} {} ", self.at.as_secs(), self.at.subsec_micros(), self.direction.marker())?;
+        for byte in &self.bytes {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for CaptureRecord {
+    type Err = &'static str;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut parts = line.split_whitespace();
+        let at = parts.next().ok_or("missing timestamp")?
+            .parse::<f64>().ok()
+            .filter(|at| at.is_finite() && *at >= 0.0)
+            .ok_or("bad timestamp")?;
+        let direction = match parts.next() {
+        Some("<") => Direction::Inbound,
+        Some(">") => Direction::Outbound,
+        _ => return Err("bad direction"),
+        };
+        let hex = parts.next().unwrap_or("");
+        if parts.next().is_some() {
+            return Err("trailing data");
+        }
+        let bytes = hex.as_bytes().chunks(2)
+            .map(|pair| match pair {
+            [hi, lo] => Ok(hex_digit(*hi)? << 4 | hex_digit(*lo)?),
+            _ => Err("odd number of hex digits"),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { at: Duration::from_secs_f64(at), direction, bytes })
+    }
+}
+
+fn hex_digit(digit: u8) -> Result<u8, &'static str> {
+    (digit as char).to_digit(16).map(|d| d as u8).ok_or("bad hex byte")
+}
+
+#[derive(Debug)]
+pub enum CaptureError {
+    Io(io::Error),
+    NotACapture,
+    BadRecord { line: usize, reason: &'static str },
+}
+
+impl std::fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+        CaptureError::Io(err) => write!(f, "capture i/o error: {}", err),
+        CaptureError::NotACapture => write!(f, "not a capture file, expected \"{}\"", MAGIC),
+        CaptureError::BadRecord { line, reason } => write!(f, "bad capture record at line {}: {}", line, reason),
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {}
+
+impl From<io::Error> for CaptureError {
+    fn from(err: io::Error) -> Self {
+        CaptureError::Io(err)
+    }
+}
+
+/// Writes a capture, shared by every clone.
+///
+/// Each record is flushed as soon as it is written, so the capture stays
+/// usable when the program dies in the middle of a ride.
+#[derive(Clone)]
+pub struct Recorder {
+    out: Arc<Mutex<Box<dyn Write + Send>>>,
+    start: Instant,
+}
+
+impl std::fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Recorder").field("start", &self.start).finish_non_exhaustive()
+    }
+}
+
+impl Recorder {
+    pub fn new<W: Write + Send + 'static>(out: W) -> io::Result<Self> {
+        let mut out: Box<dyn Write + Send> = Box::new(out);
+        writeln!(out, "{}", MAGIC)?;
+        out.flush()?;
+        Ok(Self { out: Arc::new(Mutex::new(out)), start: Instant::now() })
+    }
+
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+
+    /// Appends `bytes` stamped with the time since the capture started.
+    pub fn record(&self, direction: Direction, bytes: &[u8]) -> io::Result<()> {
+        let record = CaptureRecord { at: self.start.elapsed(), direction, bytes: bytes.to_vec() };
+        let mut out = self.out.lock().unwrap();
+        writeln!(out, "{}", record)?;
+        out.flush()
+    }
+}
+
+/// Reads the records of a capture back, in file order.
+#[derive(Debug)]
+pub struct CaptureReader<R: BufRead> {
+    input: R,
+    line: usize,
+}
+
+impl CaptureReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, CaptureError> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: BufRead> CaptureReader<R> {
+    pub fn new(mut input: R) -> Result<Self, CaptureError> {
+        let mut magic = String::new();
+        input.read_line(&mut magic)?;
+        if magic.trim_end() != MAGIC {
+            return Err(CaptureError::NotACapture);
+        }
+        Ok(Self { input, line: 1 })
+    }
+
+    /// Every remaining record, failing on the first bad one.
+    pub fn read_all(self) -> Result<Vec<CaptureRecord>, CaptureError> {
+        self.collect()
+    }
+}
+
+impl<R: BufRead> Iterator for CaptureReader<R> {
+    type Item = Result<CaptureRecord, CaptureError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let mut line = String::new();
+            match self.input.read_line(&mut line) {
+            Ok(0) => return None,
+            Ok(_) => (),
+            Err(err) => return Some(Err(err.into())),
+            }
+            self.line += 1;
+            let line = line.trim();
+            // blank lines and comments are allowed, bug reports get annotated by hand
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            return Some(line.parse().map_err(|reason| CaptureError::BadRecord { line: self.line, reason }));
+        }
+    }
+}
+
+#[test]
+fn test_capture_round_trip() {
+    let path = std::env::temp_dir().join(format!("gotway_capture_{}.txt", std::process::id()));
+    let recorder = Recorder::create(&path).unwrap();
+    recorder.record(Direction::Inbound, &[0x55, 0xaa, 0x16, 0xc2]).unwrap();
+    recorder.clone().record(Direction::Outbound, b"b").unwrap();
+    recorder.record(Direction::Inbound, &[]).unwrap();
+
+    let records = CaptureReader::open(&path).unwrap().read_all().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(records.len(), 3);
+    assert_eq!((records[0].direction, records[0].bytes.as_slice()), (Direction::Inbound, &[0x55, 0xaa, 0x16, 0xc2][..]));
+    assert_eq!((records[1].direction, records[1].bytes.as_slice()), (Direction::Outbound, &b"b"[..]));
+    assert!(records[2].bytes.is_empty());
+    assert!(records[0].at <= records[1].at && records[1].at <= records[2].at);
+
+    let record = CaptureRecord { at: Duration::from_micros(12_345_678), direction: Direction::Outbound, bytes: b"WM".to_vec() };
+    assert_eq!(record.to_string(), "12.345678 > 574d");
+    assert_eq!("12.345678 > 574d".parse(), Ok(record));
+
+    let text = format!("{}\n\n# wheel beeped here\n0.5 < 5a5a\n1.0 < 5g\n", MAGIC);
+    let mut reader = CaptureReader::new(text.as_bytes()).unwrap();
+    assert_eq!(reader.next().unwrap().unwrap().bytes, vec![0x5a, 0x5a]);
+    assert!(matches!(reader.next(), Some(Err(CaptureError::BadRecord { line: 5, .. }))));
+    assert!(reader.next().is_none());
+    assert!(matches!(CaptureReader::new("0.0 < 00\n".as_bytes()), Err(CaptureError::NotACapture)));
+}
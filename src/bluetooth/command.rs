@@ -0,0 +1,125 @@
+use std::time::Duration;
+
+use super::frame::{PedalMode, SpeedAlarmMode, RollAngle};
+use super::transport::TransportError;
+
+/// Commands of the GotWay/Begode ASCII protocol, as sent by the WheelLog app.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WheelCommand {
+    Beep,
+    PedalMode(PedalMode),
+    SpeedAlarms(SpeedAlarmMode),
+    RollAngle(RollAngle),
+    /// Speed and distance units of the wheel, `true` for miles.
+    Miles(bool),
+    /// LED strip mode, 0 to 9.
+    LedMode(u8),
+    /// 0 off, 1 on, 2 strobe.
+    LightMode(u8),
+    /// Tiltback speed in km/h, 1 to 99, `None` disables tiltback.
+    TiltbackSpeed(Option<u8>),
+    /// Gyroscope calibration, the wheel must stand upright and still.
+    Calibrate,
+}
+
+#[derive(Debug)]
+pub enum CommandError {
+    InvalidLedMode(u8),
+    InvalidLightMode(u8),
+    InvalidTiltbackSpeed(u8),
+    Transport(TransportError),
+    /// Sent, but the wheel did not report the new setting in time.
+    NotConfirmed,
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+        CommandError::InvalidLedMode(mode) => write!(f, "LED mode {} is not 0 to 9", mode),
+        CommandError::InvalidLightMode(mode) => write!(f, "light mode {} is not 0 to 2", mode),
+        CommandError::InvalidTiltbackSpeed(kmh) => write!(f, "tiltback speed {} km/h is not 1 to 99", kmh),
+        CommandError::Transport(err) => write!(f, "command not sent: {}", err),
+        CommandError::NotConfirmed => write!(f, "the wheel did not confirm the command"),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+impl From<TransportError> for CommandError {
+    fn from(err: TransportError) -> Self {
+        CommandError::Transport(err)
+    }
+}
+
+impl WheelCommand {
+    /// Wait between two writes to the wheel.
+    pub const PACING: Duration = Duration::from_millis(100);
+
+    /// Bytes of the command, one entry per write.
+    pub fn steps(&self) -> Result<Vec<Vec<u8>>, CommandError> {
+        let step = |bytes: &[u8]| bytes.to_vec();
+        let steps = match *self {
+        WheelCommand::Beep => vec![step(b"b")],
+        WheelCommand::PedalMode(mode) => vec![step(match mode {
+            PedalMode::Hard => b"h",
+            PedalMode::Medium => b"f",
+            PedalMode::Soft => b"s",
+            PedalMode::Comfort => b"i",
+            })],
+        WheelCommand::SpeedAlarms(mode) => vec![step(match mode {
+            SpeedAlarmMode::Both => b"u",
+            SpeedAlarmMode::Second => b"i",
+            SpeedAlarmMode::Off => b"o",
+            SpeedAlarmMode::Pwm => b"I",
+            })],
+        WheelCommand::RollAngle(angle) => vec![step(match angle {
+            RollAngle::Low => b">",
+            RollAngle::Medium => b"=",
+            RollAngle::High => b"<",
+            })],
+        WheelCommand::Miles(miles) => vec![step(if miles {b"m"} else {b"g"})],
+        WheelCommand::LedMode(mode @ 0..=9) => vec![step(b"WM"), vec![b'0' + mode], step(b"b")],
+        WheelCommand::LedMode(mode) => return Err(CommandError::InvalidLedMode(mode)),
+        WheelCommand::LightMode(mode) => vec![step(match mode {
+            0 => b"E",
+            1 => b"Q",
+            2 => b"T",
+            _ => return Err(CommandError::InvalidLightMode(mode)),
+            })],
+        WheelCommand::TiltbackSpeed(None) => vec![step(b"\"")],
+        WheelCommand::TiltbackSpeed(Some(kmh @ 1..=99)) => vec![
+            step(b"W"), step(b"Y"),
+            vec![b'0' + kmh / 10], vec![b'0' + kmh % 10],
+            step(b"b"), step(b"b"),
+        ],
+        WheelCommand::TiltbackSpeed(Some(kmh)) => return Err(CommandError::InvalidTiltbackSpeed(kmh)),
+        WheelCommand::Calibrate => vec![step(b"c"), step(b"y")],
+        };
+        Ok(steps)
+    }
+}
+
+#[test]
+fn test_command_bytes() {
+    let bytes = |cmd: WheelCommand| cmd.steps().unwrap().concat();
+    assert_eq!(bytes(WheelCommand::Beep), b"b");
+    assert_eq!(bytes(WheelCommand::PedalMode(PedalMode::Hard)), b"h");
+    assert_eq!(bytes(WheelCommand::PedalMode(PedalMode::Comfort)), b"i");
+    assert_eq!(bytes(WheelCommand::SpeedAlarms(SpeedAlarmMode::Off)), b"o");
+    assert_eq!(bytes(WheelCommand::RollAngle(RollAngle::High)), b"<");
+    assert_eq!(bytes(WheelCommand::Miles(true)), b"m");
+    assert_eq!(bytes(WheelCommand::Miles(false)), b"g");
+    assert_eq!(WheelCommand::LedMode(4).steps().unwrap(), vec![b"WM".to_vec(), b"4".to_vec(), b"b".to_vec()]);
+    assert_eq!(bytes(WheelCommand::LightMode(2)), b"T");
+    assert_eq!(bytes(WheelCommand::TiltbackSpeed(Some(45))), b"WY45bb");
+    assert_eq!(WheelCommand::TiltbackSpeed(Some(7)).steps().unwrap().len(), 6);
+    assert_eq!(bytes(WheelCommand::TiltbackSpeed(Some(7))), b"WY07bb");
+    assert_eq!(bytes(WheelCommand::TiltbackSpeed(None)), b"\"");
+    assert_eq!(bytes(WheelCommand::Calibrate), b"cy");
+
+    assert!(matches!(WheelCommand::LedMode(10).steps(), Err(CommandError::InvalidLedMode(10))));
+    assert!(matches!(WheelCommand::LightMode(3).steps(), Err(CommandError::InvalidLightMode(3))));
+    assert!(matches!(WheelCommand::TiltbackSpeed(Some(0)).steps(), Err(CommandError::InvalidTiltbackSpeed(0))));
+    assert!(matches!(WheelCommand::TiltbackSpeed(Some(100)).steps(), Err(CommandError::InvalidTiltbackSpeed(100))));
+}
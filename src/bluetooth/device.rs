@@ -1,10 +1,18 @@
-use super::{Peripheral, PeripheralProperties};
-use btleplug::api::Characteristic;
-use btleplug::api::Peripheral as _;
+use super::Peripheral;
 
 use super::Frame;
-use uuid::Uuid;
-const CHARACTERISTIC_UUID: Uuid = Uuid::from_u128(0x0000ffe1_0000_1000_8000_00805f9b34fb);
+use super::frame::{DecoderStats, TemperatureSensor};
+use super::telemetry::Telemetry;
+use super::capture::{Recorder, Direction};
+use super::transport::{WheelTransport, BleTransport, TransportError};
+use super::command::{WheelCommand, CommandError};
+use crate::battery::BatteryConfig;
+use crate::alarm::BeepPattern;
+use crate::locale::{tr, Phrase};
+use tokio::sync::watch;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use crate::units::{UnitSystem, Quantity, Speed, Distance, Voltage, Current, Power, Temperature};
 #[derive(Debug, Clone)]
 pub struct BlueToothInfo {
     pub name: String,
@@ -12,236 +20,454 @@ pub struct BlueToothInfo {
 }
 
 #[derive(Debug, Clone)]
-pub struct Device {
-    p: Peripheral,
-    props: PeripheralProperties,
-    char: Characteristic,
+pub struct Device<T: WheelTransport = BleTransport> {
+    transport: T,
     pub info: BlueToothInfo,
     pub euc_info: EucInfo,
     light_mode: u8,
+    telemetry: Telemetry,
+    units: Option<UnitSystem>,
+    capture: Option<Recorder>,
 }
-impl Device {
-    pub async fn new(p: Peripheral) -> Self {
-        p.is_connected().await.unwrap();
-        let (props, info) = Self::make_info(&p).await;
-        let char = Self::make_char(&p).await;
-//         let euc_info = Self::make_euc_info(&p, &char).await.unwrap();
+impl Device<BleTransport> {
+    pub async fn new(p: Peripheral) -> Result<Self, TransportError> {
+        Self::with_transport(BleTransport::new(p).await?).await
+    }
+}
+impl<T: WheelTransport> Device<T> {
+    pub async fn with_transport(transport: T) -> Result<Self, TransportError> {
+        let info = transport.info().await?;
+        let telemetry = Telemetry::start(transport.clone());
         let euc_info = Default::default();
-        Self {
-            p, props, char, info, euc_info,
+        Ok(Self {
+            transport, info, euc_info, telemetry,
             light_mode: 0,
-        }
+            units: None,
+            capture: None,
+        })
     }
-    async fn make_info(p: &Peripheral) -> (PeripheralProperties, BlueToothInfo) {
-        let props = p.properties().await.unwrap().unwrap();
-        let is_connected = p.is_connected().await.unwrap();
-        let info = BlueToothInfo {
-            name: props.local_name.clone().unwrap(),
-            is_connected,
-        };
-        (props, info)
-    }
-    async fn make_char(p: &Peripheral) -> Characteristic {
-        p.discover_services().await.unwrap();
-        let chars = p.characteristics();
-        let char = chars.into_iter().find(|c| c.uuid == CHARACTERISTIC_UUID).unwrap();
-        char
-    }
-    pub async fn update_info(&mut self) {
-        let (props, info) = Self::make_info(&self.p).await;
-        self.props = props;
-        self.info = info;
-        self.euc_info = Self::make_euc_info(&self.p, &self.char).await.unwrap();
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+    pub async fn update_info(&mut self) -> Result<(), TransportError> {
+        self.info = self.transport.info().await?;
+        self.euc_info = self.telemetry.latest();
         self.euc_info.light_mode = self.light_mode;
+        self.euc_info.units = self.units;
+        Ok(())
     }
     pub fn is_connected(&self) -> bool {
         self.info.is_connected
     }
+    /// Receiver of every info decoded by the telemetry task of this connection.
+    pub fn subscribe(&self) -> watch::Receiver<EucInfo> {
+        self.telemetry.subscribe()
+    }
+    /// Link quality counters of the frame decoder since the last reset.
+    pub fn decoder_stats(&self) -> DecoderStats {
+        self.telemetry.decoder_stats()
+    }
+    pub fn reset_decoder_stats(&mut self) -> DecoderStats {
+        self.telemetry.take_decoder_stats()
+    }
+    pub fn temperature_sensor(&self) -> TemperatureSensor {
+        self.telemetry.temperature_sensor()
+    }
+    pub fn set_temperature_sensor(&mut self, sensor: TemperatureSensor) {
+        self.telemetry.set_temperature_sensor(sensor);
+    }
+    /// Units `euc_info` is shown in, `None` to follow the wheel's own miles setting.
+    pub fn set_unit_system(&mut self, units: Option<UnitSystem>) {
+        self.units = units;
+        self.euc_info.units = units;
+    }
+    pub fn battery_config(&self) -> BatteryConfig {
+        self.telemetry.battery_config()
+    }
+    /// Sets the pack the voltage is rescaled for, applied from the next frame.
+    pub fn set_battery_config(&mut self, battery: BatteryConfig) {
+        self.telemetry.set_battery_config(battery);
+    }
+    /// Records inbound chunks and sent commands to `capture`, `None` to stop.
+    pub fn set_capture(&mut self, capture: Option<Recorder>) {
+        self.telemetry.set_capture(capture.clone());
+        self.capture = capture;
+    }
     pub async fn disconnect(self) {
-        self.p.disconnect().await.unwrap();
+        self.telemetry.stop();
+        self.transport.disconnect().await.unwrap();
         println!("Device::disconnected");
     }
-    async fn make_euc_info(p: &Peripheral, chr: &Characteristic) -> Result<EucInfo, Box<dyn std::error::Error>> {
-        use futures::StreamExt;
-        let mut frame_ab = FrameAB::default();
-        p.subscribe(&chr).await?;
-        let mut stream = p.notifications().await?;
-        let mut info = None;
-        let mut buf = Vec::new();
-        while info.is_none() {
-//             let bytes = p.read(&chr).await.map_err(|_| ())?;
-            let bytes = stream.next().await.ok_or("Больше нет значений")?.value;
-//             dbg!(&bytes);
-            buf = [buf, bytes].concat();
-            let mut bytes = buf.as_slice();
-            let frame = Frame::try_from(&mut bytes);
-            buf = Vec::from(bytes);
-//             dbg!(&frame);
-            if let Ok(frame) = frame {
-                frame_ab.set_frame(frame);
-                info = frame_ab.build();
+    async fn send_command(&self, cmd: &[u8]) -> Result<(), TransportError> {
+        if let Some(capture) = &self.capture {
+            if let Err(err) = capture.record(Direction::Outbound, cmd) {
+                eprintln!("Device: capture failed: {}", err);
             }
         }
-//         dbg!(&info);
-        Ok(info.unwrap())
-    }
-
-    async fn send_command(&self, cmd: &[u8]) {
-        self.p.write(&self.char, cmd, btleplug::api::WriteType::WithoutResponse).await.unwrap();
-    }
-    pub async fn beep(self) -> Self {
-        self.send_command(b"b").await;
-        self
-    }
-    pub async fn set_led_mode(mut self, mode: u8) -> Self {
-        self.send_command(b"WM").await;
-        self.send_command(&[mode + 0x30]).await;
-        self = self.beep().await;
-        self
-    }
-    pub async fn set_light_mode(mut self, mode: u8) -> Self {
-        self.light_mode = mode;
-        match mode {
-        0 => self.send_command(b"E").await,
-        1 => self.send_command(b"Q").await,
-        2 => self.send_command(b"T").await,
-        _ => {
-            self.send_command(b"E").await;
-            self.light_mode = 0;
+        self.transport.write(cmd).await
+    }
+    /// Sends `cmd` to the wheel, pausing [`WheelCommand::PACING`] between its writes.
+    pub async fn execute(&mut self, cmd: &WheelCommand) -> Result<(), CommandError> {
+        for (i, step) in cmd.steps()?.iter().enumerate() {
+            if i > 0 {
+                tokio::time::sleep(WheelCommand::PACING).await;
+            }
+            self.send_command(step).await?;
+        }
+        if let WheelCommand::LightMode(mode) = *cmd {
+            self.light_mode = mode;
         }
+        Ok(())
+    }
+    /// Longest wait for a FrameB reporting the outcome of a setting change.
+    pub const CONFIRM_TIMEOUT: Duration = Duration::from_secs(2);
+
+    /// Sets the tiltback speed, 1 to 99 km/h, `None` disables tiltback.
+    /// Returns once a FrameB received after the command reports the new
+    /// setting, or `CommandError::NotConfirmed` after `CONFIRM_TIMEOUT`.
+    pub async fn set_tiltback_speed(&mut self, kmh: Option<u8>) -> Result<(), CommandError> {
+        let mut info = self.subscribe();
+        let sent = Instant::now();
+        self.execute(&WheelCommand::TiltbackSpeed(kmh)).await?;
+        let confirmed = async {
+            loop {
+                {
+                    let info = info.borrow_and_update();
+                    if matches!(info.updated(Field::TiltbackSpeed), Some(at) if at > sent) && info.tiltback_speed == kmh {
+                        return true;
+                    }
+                }
+                if info.changed().await.is_err() {
+                    return false;
+                }
+            }
         };
-//         self = self.beep().await;
-        self
+        match tokio::time::timeout(Self::CONFIRM_TIMEOUT, confirmed).await {
+        Ok(true) => {
+            self.euc_info.tiltback_speed = kmh;
+            Ok(())
+        }
+        _ => Err(CommandError::NotConfirmed),
+        }
+    }
+    pub async fn beep(&mut self) -> Result<(), CommandError> {
+        self.execute(&WheelCommand::Beep).await
+    }
+    /// Sounds the wheel's buzzer `pattern.count` times, `pattern.interval` apart;
+    /// stops at the first beep the transport fails to send.
+    pub async fn beep_pattern(&self, pattern: &BeepPattern) -> Result<(), CommandError> {
+        for i in 0..pattern.count {
+            if i > 0 {
+                tokio::time::sleep(pattern.interval).await;
+            }
+            self.send_command(b"b").await?;
+        }
+        Ok(())
+    }
+    /// Modes past 9 wrap around to 0.
+    pub async fn set_led_mode(&mut self, mode: u8) -> Result<(), CommandError> {
+        let mode = if mode > 9 {0} else {mode};
+        self.execute(&WheelCommand::LedMode(mode)).await
+    }
+    /// Modes past 2 wrap around to 0.
+    pub async fn set_light_mode(&mut self, mode: u8) -> Result<(), CommandError> {
+        let mode = if mode > 2 {0} else {mode};
+        self.execute(&WheelCommand::LightMode(mode)).await
     }
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct EucInfo {
 
-    pub voltage: f32,   // Bytes 2-3:   BE voltage, fixed point, 1/100th (assumes 67.2 battery, rescale for other voltages)
-    pub speed: f32,     // Bytes 4-5:   BE speed, fixed point, 3.6 * value / 100 km/h
-    pub distance: super::frame::Distance,  // Bytes 6-9:   BE distance, 32bit fixed point, meters
-    pub current: f32,   // Bytes 10-11: BE current, signed fixed point, 1/100th amperes
-    pub temperature: super::frame::Temperature,// Bytes 12-13: BE temperature, (value / 340 + 36.53) / 100, Celsius degrees (MPU6050 native data)
+    pub voltage: Voltage,   // Bytes 2-3:   pack voltage, rescaled from the 67.2V frame value by `battery`
+    pub cell_voltage: Voltage,
+    pub battery_percent: f32,   // state of charge estimated by `battery.soc`
+    pub battery: BatteryConfig,
+    pub speed: Speed,       // Bytes 4-5:   BE speed, fixed point, 3.6 * value / 100 km/h
+    pub distance: Distance, // Bytes 6-9:   BE distance, 32bit fixed point, meters
+    pub current: Current,   // Bytes 10-11: BE current, signed fixed point, 1/100th amperes
+    pub temperature: Temperature,// Bytes 12-13: BE temperature, Celsius degrees (see `TemperatureSensor`)
 
-    pub total_distance: super::frame::Distance,    // Bytes 2-5:   BE total distance, 32bit fixed point, meters
-    pub settings: super::frame::Settings, // Byte  6:     pedals mode (high nibble), speed alarms (low nibble)
+    pub total_distance: Distance,    // Bytes 2-5:   BE total distance, 32bit fixed point, meters
+    pub settings: super::frame::Settings, // Bytes 6-7:   settings word, see `Settings`
+    pub tiltback_speed: Option<u8>, // Bytes 10-11: tiltback speed, km/h, `None` when disabled
     pub alerts: super::frame::Alerts,
     pub led_mode: u8,           // Byte  13:    LED mode
-    pub light_mode: u8,
+    pub light_mode: u8,         // Byte  15:    light mode
+
+    pub pwm: f32,               // Frame 01, Bytes 2-3: hardware PWM, %
+    pub pwm_limit: f32,         // Frame 01, Bytes 4-5: PWM tiltback limit, %
+    pub model: String,          // Frame 03, Bytes 2-13: model name
+    pub firmware: u16,          // Frame 03, Bytes 14-15: firmware version
+    pub battery_current: Current,   // Frame 07, Bytes 2-3: battery current, amperes
+    pub motor_temperature: Temperature, // Frame 07, Bytes 6-7: motor temperature
+
+    pub units: Option<UnitSystem>,  // `None` follows `settings.in_miles`
+    pub updated: HashMap<Field, Instant>,   // when each field last came in a frame
+}
+
+/// Fields of [`EucInfo`] carried by the wheel frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Field {
+    Voltage,
+    Speed,
+    Distance,
+    Current,
+    Temperature,
+    TotalDistance,
+    Settings,
+    TiltbackSpeed,
+    Alerts,
+    LedMode,
+    LightMode,
+    Pwm,
+    PwmLimit,
+    Model,
+    Firmware,
+    BatteryCurrent,
+    MotorTemperature,
+}
+
+impl Field {
+    /// Fields updated by `frame`.
+    pub fn of(frame: &Frame) -> &'static [Field] {
+        match frame {
+        Frame::FrameA{..} => &[Field::Voltage, Field::Speed, Field::Distance, Field::Current, Field::Temperature],
+        Frame::FrameB{..} => &[Field::TotalDistance, Field::Settings, Field::TiltbackSpeed, Field::Alerts, Field::LedMode, Field::LightMode],
+        Frame::FramePwm{..} => &[Field::Pwm, Field::PwmLimit],
+        Frame::FrameModel{..} => &[Field::Model, Field::Firmware],
+        Frame::FrameMotor{..} => &[Field::BatteryCurrent, Field::MotorTemperature],
+        }
+    }
 }
 
 impl std::fmt::Display for EucInfo {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f,
-"Информация о колесе:
-Скорость: {speed}
-Дистанция: {distance}
-Пробег: {total_distance}
-
-Напряежение: {voltage}
-Ток: {current}
-Температура: {temperature}
-"
-            , voltage = self.voltage
-            , speed = self.speed
-            , distance = self.distance
-            , current = self.current
-            , temperature = self.temperature
-            , total_distance = self.total_distance
-        )
+        let units = self.unit_system();
+        writeln!(f, "{}:", tr(Phrase::WheelInfo))?;
+        writeln!(f, "{}: {}", tr(Phrase::Speed), self.speed.display(units))?;
+        writeln!(f, "{}: {}", tr(Phrase::Distance), self.distance.display(units))?;
+        writeln!(f, "{}: {}", tr(Phrase::TotalDistance), self.total_distance.display(units))?;
+        writeln!(f)?;
+        writeln!(f, "{}: {}", tr(Phrase::Voltage), self.voltage.display(units))?;
+        writeln!(f, "{}: {:.0}%", tr(Phrase::Battery), self.battery_percent)?;
+        writeln!(f, "{}: {}", tr(Phrase::Current), self.current.display(units))?;
+        writeln!(f, "{}: {}", tr(Phrase::Temperature), self.temperature.display(units))
     }
 }
 
 impl EucInfo {
-    fn set_frame(self, frame: Frame) -> Self {
+    pub fn unit_system(&self) -> UnitSystem {
+        self.units.unwrap_or_else(|| UnitSystem::from_wheel(&self.settings))
+    }
+    pub fn power(&self) -> Power {
+        self.voltage * self.current
+    }
+    /// Applies one decoded frame right away: its fields and their timestamps
+    /// change, every other field keeps its last value.
+    pub fn set_frame(&mut self, frame: Frame, at: Instant) {
+        for &field in Field::of(&frame) {
+            self.updated.insert(field, at);
+        }
+        *self = std::mem::take(self).with_frame(frame);
+    }
+    /// When `field` last came from the wheel, `None` if it never did.
+    pub fn updated(&self, field: Field) -> Option<Instant> {
+        self.updated.get(&field).copied()
+    }
+    /// How old the value of `field` is.
+    pub fn age(&self, field: Field) -> Option<Duration> {
+        self.updated(field).map(|at| at.elapsed())
+    }
+    fn with_frame(self, frame: Frame) -> Self {
         match frame {
         Frame::FrameA {
             voltage, speed, distance, current, temperature
-        } => Self {voltage, speed, distance, current, temperature, ..self},
+        } => {
+            let voltage = self.battery.pack_voltage(voltage);
+            let cell_voltage = self.battery.cell_voltage(voltage);
+            let battery_percent = self.battery.percent(voltage, current);
+            Self {voltage, cell_voltage, battery_percent, speed, distance, current, temperature, ..self}
+        }
         Frame::FrameB {
-            total_distance, settings, alerts, led_mode, light_mode
-        } => Self {total_distance, settings, alerts, led_mode, light_mode, ..self},
+            total_distance, settings, tiltback_speed, alerts, led_mode, light_mode
+        } => Self {total_distance, settings, tiltback_speed, alerts, led_mode, light_mode, ..self},
+        Frame::FramePwm {
+            pwm, pwm_limit
+        } => Self {pwm, pwm_limit, ..self},
+        Frame::FrameModel {
+            model, firmware
+        } => Self {model, firmware, ..self},
+        Frame::FrameMotor {
+            battery_current, motor_temperature
+        } => Self {battery_current, motor_temperature, ..self},
         }
     }
-    fn from_frame_ab(a: Frame, b: Frame) -> Self {
-        match (a, b) {
-        (Frame::FrameA {
-            voltage, speed, distance, current, temperature
-        }, Frame::FrameB {
-            total_distance, settings, alerts, led_mode, light_mode
-        }) | (Frame::FrameB {
-            total_distance, settings, alerts, led_mode, light_mode
-        }, Frame::FrameA {
-            voltage, speed, distance, current, temperature
-        }) => Self {voltage, speed, distance, current, temperature,
-                    total_distance, settings, alerts, led_mode, light_mode},
-
-        _ => unreachable!()
-        }
+    /// Splits the info back into the FrameA/FrameB pair it is built from.
+    pub fn to_frames(&self) -> (Frame, Frame) {
+        let a = Frame::FrameA {
+            voltage: self.battery.raw_voltage(self.voltage),
+            speed: self.speed,
+            distance: self.distance,
+            current: self.current,
+            temperature: self.temperature,
+        };
+        let b = Frame::FrameB {
+            total_distance: self.total_distance,
+            settings: self.settings.clone(),
+            tiltback_speed: self.tiltback_speed,
+            alerts: self.alerts.clone(),
+            led_mode: self.led_mode,
+            light_mode: self.light_mode,
+        };
+        (a, b)
     }
 }
 
-#[derive(Default)]
-struct FrameAB {
-    a: Option<Frame>,
-    b: Option<Frame>,
-}
-impl FrameAB {
-    fn set_frame(&mut self, frame: Frame) {
-        match &frame {
-        Frame::FrameA{..} => self.a = Some(frame),
-        Frame::FrameB{..} => self.b = Some(frame),
-        }
-    }
-    fn build(&mut self) -> Option<EucInfo> {
-        match (self.a.take(), self.b.take()) {
-        (Some(a), Some(b)) => Some(EucInfo::from_frame_ab(a,b)),
-        (a, b) => {
-            self.a = a;
-            self.b = b;
-            None
-        },
-        }
-    }
-}
 #[test]
 fn test_unpacket() {
-    let mut bytes = [85, 170, 22, 194, 0, 0, 0, 0, 0, 0, 255, 66, 240, 237, 0, 1, 255, 248, 0, 24];
+    use super::frame::FrameError;
+    let bytes = [85, 170, 22, 194, 0, 0, 0, 0, 0, 0, 255, 66, 240, 237, 0, 1, 255, 248, 0, 24];
     let mut bytes = bytes.as_slice();
     let frame = Frame::try_from(&mut bytes);
-    assert_eq!(frame, Err(()));
+    assert_eq!(frame, Err(FrameError::Incomplete { available: 20 }));
 
-    let mut bytes = [Vec::from(bytes), vec![90, 90, 90, 90, 85, 170, 1, 22, 150, 186, 40, 0, 2, 208, 0, 57, 0, 0, 0, 7]].concat();
+    let bytes = [Vec::from(bytes), vec![90, 90, 90, 90, 85, 170, 1, 22, 150, 186, 40, 0, 2, 208, 0, 57, 0, 0, 0, 7]].concat();
     let mut bytes = bytes.as_slice();
     dbg!(bytes.len(), bytes[18]);
     let frame = Frame::try_from(&mut bytes);
     assert_eq!(frame, Ok(Frame::FrameA {
-            voltage: 58.26,
-            speed: 0.0,
-            distance: 0.0,
-            current: -1.9,
-            temperature: 0.25179997,
+            voltage: Voltage(58.26),
+            speed: Speed(0.0),
+            distance: Distance(0.0),
+            current: Current(-1.9),
+            temperature: Temperature(-3859.0 / 340.0 + 36.53),
         })
     );
 
-    let mut bytes = [Vec::from(bytes), vec![0, 8, 4, 24, 90, 90, 90, 90]].concat();
+    let bytes = [Vec::from(bytes), vec![0, 8, 4, 24, 90, 90, 90, 90]].concat();
     let mut bytes = bytes.as_slice();
     dbg!(bytes.len(), bytes[18]);
     let frame = Frame::try_from(&mut bytes);
     assert_eq!(frame, Ok(Frame::FrameB {
-            total_distance: 18257594.0,
+            total_distance: Distance(18257594.0),
             settings: super::frame::Settings {
-                pedals_mode: None,
-                speedAlarms: 2,
-                rollAngle: 0,
-                inMiles: false,
+                pedals_mode: super::frame::PedalMode::Medium,
+                speed_alarms: super::frame::SpeedAlarmMode::Off,
+                roll_angle: super::frame::RollAngle::Low,
+                in_miles: false,
             },
+            tiltback_speed: Some(57),
             alerts: super::frame::Alerts::default(),
             led_mode: 0,
             light_mode: 7,
         })
-    )
+    );
+
+    let mut bytes: &[u8] = &[85, 170, 22, 194, 0, 0, 0, 0, 0, 0, 255, 66, 240, 237, 0, 1, 255, 248, 0, 24, 90, 90, 0, 90];
+    assert_eq!(Frame::try_from(&mut bytes), Err(FrameError::BadFooter { offset: 20, found: [90, 90, 0, 90] }));
+    let mut bytes: &[u8] = &[85, 170, 22, 194, 0, 0, 0, 0, 0, 0, 255, 66, 240, 237, 0, 1, 255, 248, 2, 24, 90, 90, 90, 90];
+    assert_eq!(Frame::try_from(&mut bytes), Err(FrameError::UnknownType { offset: 18, frame_type: 2 }));
+    let mut bytes: &[u8] = &[0, 1, 2];
+    assert_eq!(Frame::try_from(&mut bytes), Err(FrameError::NoHeader));
 
 //     assert!(false);
 }
+
+#[test]
+fn test_set_frame() {
+    let frame_a = super::frame::TEST_FRAME_A;
+    let frame_b = super::frame::TEST_FRAME_B;
+    let start = Instant::now();
+    let mut info = EucInfo::default();
+
+    info.set_frame(Frame::try_from(&frame_a).unwrap(), start);
+    assert_eq!(info.voltage, Voltage(58.26));
+    assert_eq!(info.updated(Field::Speed), Some(start));
+    assert_eq!(info.updated(Field::TotalDistance), None);
+
+    let later = start + Duration::from_millis(50);
+    info.set_frame(Frame::try_from(&frame_b).unwrap(), later);
+    assert_eq!(info.voltage, Voltage(58.26));
+    assert_eq!(info.total_distance, Distance(18257594.0));
+    assert_eq!(info.updated(Field::Speed), Some(start));
+    assert_eq!(info.updated(Field::TotalDistance), Some(later));
+}
+
+#[test]
+fn test_to_frames_round_trip() {
+    let frame_a = super::frame::TEST_FRAME_A;
+    let frame_b = super::frame::TEST_FRAME_B;
+    let sensor = TemperatureSensor::Mpu6500;
+    let start = Instant::now();
+    let mut info = EucInfo { battery: BatteryConfig::V84, ..Default::default() };
+    info.set_frame(Frame::parse(&frame_a, sensor).unwrap(), start);
+    info.set_frame(Frame::parse(&frame_b, sensor).unwrap(), start);
+
+    let (a, b) = info.to_frames();
+    let mut copy = EucInfo { battery: BatteryConfig::V84, ..Default::default() };
+    copy.set_frame(Frame::parse(&a.to_bytes(sensor), sensor).unwrap(), start);
+    copy.set_frame(Frame::parse(&b.to_bytes(sensor), sensor).unwrap(), start);
+    assert_eq!(copy.to_frames(), (a, b));
+    assert_eq!((copy.voltage, copy.battery_percent, copy.temperature), (info.voltage, info.battery_percent, info.temperature));
+    assert_eq!((copy.tiltback_speed, copy.light_mode), (Some(57), 7));
+}
+
+#[tokio::test]
+async fn test_device_over_memory_transport() {
+    use super::transport::MemoryTransport;
+    let frame_a = super::frame::TEST_FRAME_A;
+    let transport = MemoryTransport::new("GotWay_Test");
+    let mut device = Device::with_transport(transport.clone()).await.unwrap();
+    assert_eq!(device.info.name, "GotWay_Test");
+
+    let mut info = device.subscribe();
+    transport.subscribed().await;
+    for chunk in frame_a.chunks(20) {
+        transport.notify(chunk);
+    }
+    info.changed().await.unwrap();
+    device.update_info().await.unwrap();
+    assert_eq!(device.euc_info.voltage, Voltage(58.26));
+
+    device.beep().await.unwrap();
+    assert_eq!(transport.written(), vec![b"b".to_vec()]);
+    let start = tokio::time::Instant::now();
+    device.beep_pattern(&BeepPattern::new(3, Duration::ZERO)).await.unwrap();
+    assert_eq!(transport.written().len(), 4);
+    assert!(start.elapsed() >= BeepPattern::MIN_INTERVAL * 2);
+    transport.disconnect().await.unwrap();
+    assert!(matches!(device.beep_pattern(&BeepPattern::default()).await, Err(CommandError::Transport(_))));
+    assert_eq!(transport.written().len(), 4);
+    device.disconnect().await;
+    assert!(!transport.info().await.unwrap().is_connected);
+}
+
+#[tokio::test]
+async fn test_device_capture() {
+    use super::capture::CaptureReader;
+    use super::transport::MemoryTransport;
+    let path = std::env::temp_dir().join(format!("gotway_device_capture_{}.txt", std::process::id()));
+    let frame_a = super::frame::TEST_FRAME_A;
+    let transport = MemoryTransport::new("GotWay_Test");
+    let mut device = Device::with_transport(transport.clone()).await.unwrap();
+    device.set_capture(Some(Recorder::create(&path).unwrap()));
+
+    let mut info = device.subscribe();
+    transport.subscribed().await;
+    for chunk in frame_a.chunks(20) {
+        transport.notify(chunk);
+    }
+    info.changed().await.unwrap();
+    device.beep().await.unwrap();
+    device.disconnect().await;
+
+    let records = CaptureReader::open(&path).unwrap().read_all().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let records: Vec<_> = records.into_iter().map(|r| (r.direction, r.bytes)).collect();
+    assert_eq!(records, vec![
+        (Direction::Inbound, frame_a[..20].to_vec()),
+        (Direction::Inbound, frame_a[20..].to_vec()),
+        (Direction::Outbound, b"b".to_vec()),
+    ]);
+}
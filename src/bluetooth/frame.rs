@@ -1,72 +1,200 @@
+pub use crate::units::{Speed, Distance, Voltage, Current, Temperature};
 
-#[derive(Debug, PartialEq)]
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Frame {
     // Bytes 0-1:   frame header, 55 AA
     // Byte  18:    frame type, 00 for frame A
     // Byte  19:    18 frame footer 00
     FrameA {
-        voltage: f32,   // Bytes 2-3:   BE voltage, fixed point, 1/100th (assumes 67.2 battery, rescale for other voltages)
-        speed: f32,     // Bytes 4-5:   BE speed, fixed point, 3.6 * value / 100 km/h
-        distance: Distance,  // Bytes 6-9:   BE distance, 32bit fixed point, meters
-        current: f32,   // Bytes 10-11: BE current, signed fixed point, 1/100th amperes
-        temperature: Temperature,// Bytes 12-13: BE temperature, (value / 340 + 36.53) / 100, Celsius degrees (MPU6050 native data)
+        voltage: Voltage,   // Bytes 2-3:   BE voltage, fixed point, 1/100th (assumes 67.2 battery, rescale for other voltages)
+        speed: Speed,       // Bytes 4-5:   BE speed, fixed point, 3.6 * value / 100 km/h
+        distance: Distance, // Bytes 6-9:   BE distance, 32bit fixed point, meters
+        current: Current,   // Bytes 10-11: BE current, signed fixed point, 1/100th amperes
+        temperature: Temperature,// Bytes 12-13: BE temperature, raw IMU data, see `TemperatureSensor`
     },
     // Byte  18:    frame type, 04 for frame B
     FrameB {
         total_distance: Distance,    // Bytes 2-5:   BE total distance, 32bit fixed point, meters
-        settings: Settings,     // Byte  6-7:     pedals mode (high nibble), speed alarms (low nibble)
+        settings: Settings,     // Bytes 6-7:   BE settings word, see `Settings`
+        tiltback_speed: Option<u8>, // Bytes 10-11: BE tiltback speed, km/h, 0 or 100 and over when disabled
         alerts: Alerts,
         led_mode: u8,           // Byte  13:    LED mode
-        light_mode: u8,
-    }
+        light_mode: u8,         // Byte  15:    light mode, 0 off, 1 on, 2 strobe
+    },
+    // Byte  18:    frame type, 01 for the PWM frame (newer Begode boards)
+    FramePwm {
+        pwm: f32,       // Bytes 2-3:   BE hardware PWM (motor load), signed fixed point, 1/100th %
+        pwm_limit: f32, // Bytes 4-5:   BE PWM tiltback limit, fixed point, 1/100th %
+    },
+    // Byte  18:    frame type, 03 for the model/firmware frame
+    FrameModel {
+        model: String,  // Bytes 2-13:  ASCII model name, zero padded
+        firmware: u16,  // Bytes 14-15: BE firmware version
+    },
+    // Byte  18:    frame type, 07 for the battery/motor frame
+    FrameMotor {
+        battery_current: Current,       // Bytes 2-3:   BE battery current, signed fixed point, 1/100th amperes
+        motor_temperature: Temperature, // Bytes 6-7:   BE motor temperature, signed, Celsius degrees
+    },
     // Bytes 20-23: frame footer, 5A 5A 5A 5A
 }
 
+/// Why a byte sequence could not be decoded into a [`Frame`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum FrameError {
+    /// No `55 AA` header anywhere in the input.
+    NoHeader,
+    /// A header was found but fewer than 24 bytes are available from it.
+    Incomplete { available: usize },
+    /// Bytes 0-1 are not `55 AA`.
+    BadHeader { offset: usize, found: [u8; 2] },
+    /// Bytes 20-23 are not `5A 5A 5A 5A`.
+    BadFooter { offset: usize, found: [u8; 4] },
+    /// Byte 18 holds a frame type the decoder does not know.
+    UnknownType { offset: usize, frame_type: u8 },
+}
+
+impl FrameError {
+    /// `true` when more input may still complete a frame, `false` for corrupt input.
+    pub fn is_incomplete(&self) -> bool {
+        matches!(self, FrameError::NoHeader | FrameError::Incomplete{..})
+    }
+}
+
+impl std::fmt::Display for FrameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+        FrameError::NoHeader => write!(f, "no 55 AA frame header found"),
+        FrameError::Incomplete { available } =>
+            write!(f, "incomplete frame: {} of {} bytes", available, FRAME_LEN),
+        FrameError::BadHeader { offset, found } =>
+            write!(f, "bad frame header at byte {}: {:02X?}", offset, found),
+        FrameError::BadFooter { offset, found } =>
+            write!(f, "bad frame footer at byte {}: {:02X?}", offset, found),
+        FrameError::UnknownType { offset, frame_type } =>
+            write!(f, "unknown frame type {:#04X} at byte {}", frame_type, offset),
+        }
+    }
+}
+
+impl std::error::Error for FrameError {}
+
 impl TryFrom<&[u8; 24]> for Frame {
-    type Error = ();
-    fn try_from(bytes: &[u8; 24]) -> Result<Frame, ()> {
-        if &bytes[0..=1] != &[0x55, 0xAA]
-        || &bytes[20..=23] != &[0x5A; 4] {
-            return Err(());
+    type Error = FrameError;
+    fn try_from(bytes: &[u8; 24]) -> Result<Frame, FrameError> {
+        Frame::parse(bytes, TemperatureSensor::default())
+    }
+}
+
+impl Frame {
+    /// Decodes a 24-byte packet, converting the FrameA temperature for `sensor`.
+    pub fn parse(bytes: &[u8; 24], sensor: TemperatureSensor) -> Result<Frame, FrameError> {
+        if bytes[0..2] != HEADER {
+            return Err(FrameError::BadHeader { offset: 0, found: to_arr(&bytes[0..2]) });
+        }
+        if bytes[20..24] != FOOTER {
+            return Err(FrameError::BadFooter { offset: 20, found: to_arr(&bytes[20..24]) });
         }
         let frame = match bytes[18] {
         0x00 => Frame::FrameA {
-            voltage: u16::from_be_bytes(to_arr(&bytes[2..4])) as f32 / 100.0,
-            speed: i16::from_be_bytes(to_arr(&bytes[4..6])) as f32 * 3.6 / 100.0,
+            voltage: Voltage(u16::from_be_bytes(to_arr(&bytes[2..4])) as f32 / 100.0),
+            speed: Speed(i16::from_be_bytes(to_arr(&bytes[4..6])) as f32 * 3.6 / 100.0),
             distance: Distance(u32::from_be_bytes(to_arr(&bytes[6..10])) as f32),
-            current: i16::from_be_bytes(to_arr(&bytes[10..12])) as f32 / 100.0,
-//             temperature: (i16::from_be_bytes(to_arr(&bytes[12..14])) as f32 / 340.0 + 36.53) * 100.0,
-//             temperature: (i16::from_be_bytes(to_arr(&bytes[12..14])) as f32 / 333.87 + 21.00) * 100.0,
-            temperature: Temperature(i16::from_be_bytes(to_arr(&bytes[12..14])) as f32 / 340.0 + 36.53),
+            current: Current(i16::from_be_bytes(to_arr(&bytes[10..12])) as f32 / 100.0),
+            temperature: Temperature(sensor.celsius(i16::from_be_bytes(to_arr(&bytes[12..14])))),
         },
         0x04 => Frame::FrameB {
             total_distance: Distance(u32::from_be_bytes(bytes[2..6].try_into().unwrap()) as f32),
             settings: Settings::from(u16::from_be_bytes(bytes[6..8].try_into().unwrap())),
+            tiltback_speed: match u16::from_be_bytes(to_arr(&bytes[10..12])) {
+                kmh @ 1..=99 => Some(kmh as u8),
+                _ => None,
+                },
             alerts: Alerts::from(bytes[12]),
             led_mode: bytes[13],
-            light_mode: bytes[14],
+            light_mode: bytes[15],
+        },
+        0x01 => Frame::FramePwm {
+            pwm: i16::from_be_bytes(to_arr(&bytes[2..4])) as f32 / 100.0,
+            pwm_limit: u16::from_be_bytes(to_arr(&bytes[4..6])) as f32 / 100.0,
+        },
+        0x03 => Frame::FrameModel {
+            model: bytes[2..14].iter().take_while(|&&b| b != 0).map(|&b| b as char).collect(),
+            firmware: u16::from_be_bytes(to_arr(&bytes[14..16])),
         },
-        _ => return Err(()),
+        0x07 => Frame::FrameMotor {
+            battery_current: Current(i16::from_be_bytes(to_arr(&bytes[2..4])) as f32 / 100.0),
+            motor_temperature: Temperature(i16::from_be_bytes(to_arr(&bytes[6..8])) as f32),
+        },
+        frame_type => return Err(FrameError::UnknownType { offset: 18, frame_type }),
         };
         Ok(frame)
     }
 }
 
 impl <'a, 'b> TryFrom<&'a mut &'b [u8]> for Frame {
-    type Error = ();
-    fn try_from(mut bytes: &'a mut &'b [u8]) -> Result<Frame, ()> {
-        let pos = bytes.array_chunks().position(|c| c == &[0x55, 0xAA]).ok_or(())? * 2;
+    type Error = FrameError;
+    fn try_from(bytes: &'a mut &'b [u8]) -> Result<Frame, FrameError> {
+        let pos = find_header(bytes).ok_or(FrameError::NoHeader)?;
         *bytes = &bytes[pos..];
-        if bytes.len()>=24 {
-            if let Ok(frame) = Frame::try_from(&bytes.as_chunks::<24>().0[0]) {
-                *bytes = &bytes[24..];
-                Ok(frame)
-            } else {
-                Err(())
+        if bytes.len() < FRAME_LEN {
+            return Err(FrameError::Incomplete { available: bytes.len() });
+        }
+        let frame = Frame::try_from(&bytes.as_chunks::<FRAME_LEN>().0[0])?;
+        *bytes = &bytes[FRAME_LEN..];
+        Ok(frame)
+    }
+}
+
+impl Frame {
+    /// Encodes the frame back into a 24-byte GotWay packet, converting the
+    /// FrameA temperature for `sensor` (`Auto` encodes like `Mpu6050`).
+    ///
+    /// Bytes the decoder ignores are left zeroed, so `Frame::parse(&frame.to_bytes(sensor), sensor)`
+    /// gives back an equal frame.
+    pub fn to_bytes(&self, sensor: TemperatureSensor) -> [u8; 24] {
+        let mut bytes = [0u8; FRAME_LEN];
+        bytes[0..2].copy_from_slice(&HEADER);
+        match self {
+        Frame::FrameA { voltage, speed, distance, current, temperature } => {
+            bytes[2..4].copy_from_slice(&((voltage.0 * 100.0).round() as u16).to_be_bytes());
+            bytes[4..6].copy_from_slice(&((speed.0 * 100.0 / 3.6).round() as i16).to_be_bytes());
+            bytes[6..10].copy_from_slice(&(distance.0 as u32).to_be_bytes());
+            bytes[10..12].copy_from_slice(&((current.0 * 100.0).round() as i16).to_be_bytes());
+            bytes[12..14].copy_from_slice(&sensor.raw(temperature.0).to_be_bytes());
+            bytes[18] = 0x00;
+        }
+        Frame::FrameB { total_distance, settings, tiltback_speed, alerts, led_mode, light_mode } => {
+            bytes[2..6].copy_from_slice(&(total_distance.0 as u32).to_be_bytes());
+            bytes[6..8].copy_from_slice(&u16::from(settings).to_be_bytes());
+            bytes[10..12].copy_from_slice(&(tiltback_speed.unwrap_or(100) as u16).to_be_bytes());
+            bytes[12] = u8::from(alerts);
+            bytes[13] = *led_mode;
+            bytes[15] = *light_mode;
+            bytes[18] = 0x04;
+        }
+        Frame::FramePwm { pwm, pwm_limit } => {
+            bytes[2..4].copy_from_slice(&((pwm * 100.0).round() as i16).to_be_bytes());
+            bytes[4..6].copy_from_slice(&((pwm_limit * 100.0).round() as u16).to_be_bytes());
+            bytes[18] = 0x01;
+        }
+        Frame::FrameModel { model, firmware } => {
+            for (dst, c) in bytes[2..14].iter_mut().zip(model.chars()) {
+                *dst = c as u8;
             }
-        } else {
-            Err(())
+            bytes[14..16].copy_from_slice(&firmware.to_be_bytes());
+            bytes[18] = 0x03;
+        }
+        Frame::FrameMotor { battery_current, motor_temperature } => {
+            bytes[2..4].copy_from_slice(&((battery_current.0 * 100.0).round() as i16).to_be_bytes());
+            bytes[6..8].copy_from_slice(&(motor_temperature.0.round() as i16).to_be_bytes());
+            bytes[18] = 0x07;
         }
+        }
+        bytes[19] = 0x18;
+        bytes[20..24].copy_from_slice(&FOOTER);
+        bytes
     }
 }
 
@@ -74,33 +202,259 @@ fn to_arr<const N: usize>(arr: &[u8]) -> [u8; N] {
     arr.try_into().unwrap()
 }
 
+const HEADER: [u8; 2] = [0x55, 0xAA];
+const FOOTER: [u8; 4] = [0x5A; 4];
+const FRAME_LEN: usize = 24;
+
+/// Position of the first `55 AA` header in `bytes`, at any offset.
+fn find_header(bytes: &[u8]) -> Option<usize> {
+    bytes.windows(2).position(|w| w == HEADER)
+}
+
+/// Counters collected by [`FrameDecoder`] while it resynchronises on the stream.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DecoderStats {
+    pub frames: usize,          // complete frames handed out
+    pub bytes_discarded: usize, // garbage skipped while looking for a `55 AA` header
+    pub bad_footers: usize,     // headers whose bytes 20-23 were not `5A 5A 5A 5A`
+    pub unknown_types: usize,   // well-formed frames with an unsupported type byte
+}
+
+/// Streaming decoder fed with raw BLE notification chunks.
+///
+/// Chunks may split a frame anywhere; the decoder keeps the tail in its own
+/// buffer and hands out frames once all 24 bytes have arrived.
+#[derive(Debug, Clone, Default)]
+pub struct FrameDecoder {
+    buf: Vec<u8>,
+    stats: DecoderStats,
+    sensor: TemperatureSensor,
+    detected: Option<TemperatureSensor>,    // resolved `Auto` sensor
+    candidate: Option<(TemperatureSensor, usize)>,  // sensor the last readings agree on, and how many
+}
+
+impl FrameDecoder {
+    /// FrameAs that must agree on the sensor before `Auto` is resolved.
+    /// A hot MPU6050 board reads as a plausible MPU6500 only, so one reading is not enough.
+    pub const DETECT_READINGS: usize = 50;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_sensor(sensor: TemperatureSensor) -> Self {
+        Self { sensor, ..Self::default() }
+    }
+
+    pub fn set_sensor(&mut self, sensor: TemperatureSensor) {
+        self.sensor = sensor;
+        self.detected = None;
+        self.candidate = None;
+    }
+
+    /// Sensor the temperature is currently decoded for;
+    /// `Auto` until [`FrameDecoder::DETECT_READINGS`] plausible readings have settled it.
+    pub fn sensor(&self) -> TemperatureSensor {
+        self.detected.unwrap_or(self.sensor)
+    }
+
+    /// Appends a notification chunk to the internal buffer.
+    pub fn push(&mut self, chunk: &[u8]) {
+        self.buf.extend_from_slice(chunk);
+    }
+
+    /// Appends `chunk` and returns every frame completed by it.
+    ///
+    /// Rejected frames are only counted in [`DecoderStats`]; use
+    /// [`FrameDecoder::next_frame`] to see why they were rejected.
+    pub fn decode(&mut self, chunk: &[u8]) -> Vec<Frame> {
+        self.push(chunk);
+        let mut frames = Vec::new();
+        loop {
+            match self.next_frame() {
+            Ok(frame) => frames.push(frame),
+            Err(err) if err.is_incomplete() => return frames,
+            Err(_) => {}
+            }
+        }
+    }
+
+    /// Next complete frame from the buffer.
+    ///
+    /// An error for which [`FrameError::is_incomplete`] holds means more bytes
+    /// are needed. Any other error reports corrupt input that has already been
+    /// skipped, so the caller can simply ask again: garbage before a header is
+    /// dropped, a header with a bad footer is skipped by one byte and frames
+    /// of unknown type are skipped whole.
+    pub fn next_frame(&mut self) -> Result<Frame, FrameError> {
+        let pos = match find_header(&self.buf) {
+            Some(pos) => pos,
+            None => {
+                // Keep a trailing 0x55, it may be the first half of a header.
+                let keep = (self.buf.last() == Some(&HEADER[0])) as usize;
+                self.discard(self.buf.len() - keep);
+                return Err(FrameError::NoHeader);
+            }
+        };
+        self.discard(pos);
+        if self.buf.len() < FRAME_LEN {
+            return Err(FrameError::Incomplete { available: self.buf.len() });
+        }
+        let bytes: [u8; FRAME_LEN] = to_arr(&self.buf[..FRAME_LEN]);
+        match Frame::parse(&bytes, self.sensor()) {
+        Ok(frame) => {
+            self.buf.drain(..FRAME_LEN);
+            self.stats.frames += 1;
+            if let Frame::FrameA{..} = frame {
+                self.detect_sensor(i16::from_be_bytes(to_arr(&bytes[12..14])));
+            }
+            Ok(frame)
+        }
+        Err(err @ FrameError::UnknownType{..}) => {
+            self.buf.drain(..FRAME_LEN);
+            self.stats.unknown_types += 1;
+            Err(err)
+        }
+        Err(err) => {
+            self.stats.bad_footers += 1;
+            self.discard(1);
+            Err(err)
+        }
+        }
+    }
+
+    /// Bytes received but not yet consumed.
+    pub fn buffered(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn stats(&self) -> &DecoderStats {
+        &self.stats
+    }
+
+    /// Returns the counters collected so far and starts over from zero.
+    pub fn take_stats(&mut self) -> DecoderStats {
+        std::mem::take(&mut self.stats)
+    }
+
+    /// Counts the raw temperature of a valid FrameA towards resolving `Auto`.
+    /// Readings plausible for both sensors or neither are skipped, a reading
+    /// for the other sensor starts the count over.
+    fn detect_sensor(&mut self, raw: i16) {
+        if self.sensor != TemperatureSensor::Auto || self.detected.is_some() {
+            return;
+        }
+        if let Some(sensor) = TemperatureSensor::detect(raw) {
+            let count = match self.candidate {
+            Some((candidate, count)) if candidate == sensor => count + 1,
+            _ => 1,
+            };
+            self.candidate = Some((sensor, count));
+            if count >= Self::DETECT_READINGS {
+                self.detected = Some(sensor);
+            }
+        }
+    }
+
+    fn discard(&mut self, n: usize) {
+        self.buf.drain(..n);
+        self.stats.bytes_discarded += n;
+    }
+}
+
+/// Settings word of FrameB, bytes 6-7:
+///
+/// | bits  | meaning          |
+/// |-------|------------------|
+/// | 13-14 | pedals mode      |
+/// | 10-11 | speed alarm mode |
+/// | 7-8   | roll angle       |
+/// | 0     | miles units      |
 #[derive(Debug, Clone, Default, PartialEq)]
 pub struct Settings {
-    pub pedals_mode: Option<PedalMode>,
-    pub speedAlarms: u8,
-    pub rollAngle: u8,
-    pub inMiles: bool,
+    pub pedals_mode: PedalMode,
+    pub speed_alarms: SpeedAlarmMode,
+    pub roll_angle: RollAngle,
+    pub in_miles: bool,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
 pub enum PedalMode {
-    HighNibble,
-    LowNibble,
+    Hard,
+    Medium,
+    #[default]
+    Soft,
+    Comfort,    // newer firmware only
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SpeedAlarmMode {
+    #[default]
+    Both,       // first and second speed alarms
+    Second,     // second speed alarm only
+    Off,
+    Pwm,        // PWM based alarm, newer firmware only
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum RollAngle {
+    #[default]
+    Low,
+    Medium,
+    High,
 }
+
 impl From<u16> for Settings {
     fn from(settings: u16) -> Settings {
-        let pedalsMode = ((settings >> 13) & 0x03) as u8;
-        let speedAlarms = ((settings >> 10) & 0x03) as u8;
-        let rollAngle = ((settings >> 7) & 0x03) as u8;
-        let inMiles = settings == 1;
+        let pedals_mode = match (settings >> 13) & 0x03 {
+        0 => PedalMode::Soft,
+        1 => PedalMode::Medium,
+        2 => PedalMode::Hard,
+        _ => PedalMode::Comfort,
+        };
+        let speed_alarms = match (settings >> 10) & 0x03 {
+        0 => SpeedAlarmMode::Both,
+        1 => SpeedAlarmMode::Second,
+        2 => SpeedAlarmMode::Off,
+        _ => SpeedAlarmMode::Pwm,
+        };
+        // 3 is not used by the firmware
+        let roll_angle = match (settings >> 7) & 0x03 {
+        0 => RollAngle::Low,
+        1 => RollAngle::Medium,
+        _ => RollAngle::High,
+        };
+        let in_miles = settings & 0x01 == 1;
 
         Settings {
-            pedals_mode: None,
-            speedAlarms, rollAngle, inMiles,
+            pedals_mode, speed_alarms, roll_angle, in_miles,
         }
     }
 }
 
+impl From<&Settings> for u16 {
+    fn from(settings: &Settings) -> u16 {
+        let pedals_mode = match settings.pedals_mode {
+        PedalMode::Soft => 0,
+        PedalMode::Medium => 1,
+        PedalMode::Hard => 2,
+        PedalMode::Comfort => 3,
+        };
+        let speed_alarms = match settings.speed_alarms {
+        SpeedAlarmMode::Both => 0,
+        SpeedAlarmMode::Second => 1,
+        SpeedAlarmMode::Off => 2,
+        SpeedAlarmMode::Pwm => 3,
+        };
+        let roll_angle = match settings.roll_angle {
+        RollAngle::Low => 0,
+        RollAngle::Medium => 1,
+        RollAngle::High => 2,
+        };
+        pedals_mode << 13 | speed_alarms << 10 | roll_angle << 7 | settings.in_miles as u16
+    }
+}
+
 #[derive(Debug, Clone, Default, PartialEq)]
 pub struct Alerts(Vec<Alert>);
 
@@ -148,36 +502,264 @@ impl From<u8> for Alerts {
     }
 }
 
-#[derive(Debug, Clone, Default, PartialEq)]
-pub struct Temperature(f32);
+impl Alerts {
+    pub fn iter(&self) -> impl Iterator<Item = &Alert> {
+        self.0.iter()
+    }
 
-#[derive(Debug, Clone, Default, PartialEq)]
-pub struct Distance(f32);
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
 
-impl std::fmt::Display for Temperature {
+/// Space separated alert names, as the WheelLog app shows and logs them.
+impl std::fmt::Display for Alerts {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:.2} C.", self.0)
+        for (i, alert) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{:?}", alert)?;
+        }
+        Ok(())
     }
 }
 
-impl std::fmt::Display for Distance {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let txt = self.0.to_string();
-        let ln = txt.len();
-        match ln {
-        0..=3 => {
-            let m = txt;
-            write!(f, "{}m.", m)
-        }
-        4..=6 => {
-            let (km, m) = (&txt[..ln-3], &txt[ln-3..]);
-            write!(f, "{}km. {}m.", km, m)
-        }
-        7..=9 => {
-            let (kkm, km, m) = (&txt[..ln-6], &txt[ln-6..ln-3], &txt[ln-3..]);
-            write!(f, "{}kkm. {}km. {}m.", kkm, km, m)
-        },
-        _ => write!(f, "Err")
+impl From<&Alerts> for u8 {
+    fn from(alerts: &Alerts) -> u8 {
+        alerts.0.iter().fold(0, |bits, alert| bits | alert.bit())
+    }
+}
+
+impl Alert {
+    /// Mask of this alert in byte 12 of FrameB.
+    pub fn bit(&self) -> u8 {
+        match self {
+        Alert::HighPower => 0x01,
+        Alert::Speed2 => 0x02,
+        Alert::Speed1 => 0x04,
+        Alert::LowVoltage => 0x08,
+        Alert::OverVoltage => 0x10,
+        Alert::OverTemperature => 0x20,
+        Alert::errHallSensors => 0x40,
+        Alert::TransportMode => 0x80,
         }
     }
 }
+
+/// IMU chip the board reads its temperature from; each scales the raw value differently.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum TemperatureSensor {
+    #[default]
+    Mpu6050,    // value / 340 + 36.53
+    Mpu6500,    // value / 333.87 + 21.00
+    /// Picks whichever formula gives a plausible reading.
+    Auto,
+}
+
+impl TemperatureSensor {
+    /// Board temperatures a wheel can realistically report, Celsius degrees.
+    const PLAUSIBLE: std::ops::RangeInclusive<f32> = -20.0..=60.0;
+    const ROOM: f32 = 25.0;
+
+    pub fn celsius(&self, raw: i16) -> f32 {
+        match self {
+        TemperatureSensor::Mpu6050 => raw as f32 / 340.0 + 36.53,
+        TemperatureSensor::Mpu6500 => raw as f32 / 333.87 + 21.00,
+        TemperatureSensor::Auto => {
+            let (a, b) = (Self::Mpu6050.celsius(raw), Self::Mpu6500.celsius(raw));
+            Self::detect(raw).map_or(
+                if (a - Self::ROOM).abs() <= (b - Self::ROOM).abs() {a} else {b},
+                |sensor| sensor.celsius(raw))
+        }
+        }
+    }
+
+    /// Raw IMU value for `celsius`, the inverse of [`TemperatureSensor::celsius`].
+    pub fn raw(&self, celsius: f32) -> i16 {
+        match self {
+        TemperatureSensor::Mpu6500 => ((celsius - 21.00) * 333.87).round() as i16,
+        _ => ((celsius - 36.53) * 340.0).round() as i16,
+        }
+    }
+
+    /// The only sensor whose formula gives a plausible reading for `raw`,
+    /// `None` when both or neither do.
+    pub fn detect(raw: i16) -> Option<TemperatureSensor> {
+        let mpu6050 = Self::PLAUSIBLE.contains(&Self::Mpu6050.celsius(raw));
+        let mpu6500 = Self::PLAUSIBLE.contains(&Self::Mpu6500.celsius(raw));
+        match (mpu6050, mpu6500) {
+        (true, false) => Some(Self::Mpu6050),
+        (false, true) => Some(Self::Mpu6500),
+        _ => None,
+        }
+    }
+}
+
+/// FrameA and FrameB of a real wheel, shared by the tests of the crate.
+#[cfg(test)]
+pub(crate) const TEST_FRAME_A: [u8; 24] = [85, 170, 22, 194, 0, 0, 0, 0, 0, 0, 255, 66, 240, 237, 0, 1, 255, 248, 0, 24, 90, 90, 90, 90];
+#[cfg(test)]
+pub(crate) const TEST_FRAME_B: [u8; 24] = [85, 170, 1, 22, 150, 186, 40, 0, 2, 208, 0, 57, 0, 0, 0, 7, 0, 8, 4, 24, 90, 90, 90, 90];
+
+#[test]
+fn test_decoder_resync() {
+    let frame_a = TEST_FRAME_A;
+    let frame_b = TEST_FRAME_B;
+    let mut unknown = frame_a;
+    unknown[18] = 0x09;
+    let mut bad_footer = frame_a;
+    bad_footer[23] = 0;
+
+    let stream = [&[1, 2, 3][..], &frame_a, &bad_footer, &unknown, &[0x55], &frame_b].concat();
+    let mut decoder = FrameDecoder::new();
+    let frames: Vec<Frame> = stream.chunks(20).flat_map(|c| decoder.decode(c)).collect();
+
+    assert_eq!(frames.len(), 2);
+    assert!(matches!(frames[0], Frame::FrameA{..}));
+    assert!(matches!(frames[1], Frame::FrameB{..}));
+    assert_eq!(decoder.buffered(), 0);
+    assert_eq!(decoder.stats(), &DecoderStats {
+        frames: 2,
+        bytes_discarded: 3 + 24 + 1,
+        bad_footers: 1,
+        unknown_types: 1,
+    });
+}
+
+#[test]
+fn test_decoder_incomplete() {
+    let frame_a = TEST_FRAME_A;
+    let mut decoder = FrameDecoder::new();
+    assert!(decoder.decode(&frame_a[..1]).is_empty());
+    assert!(decoder.decode(&frame_a[1..23]).is_empty());
+    assert_eq!(decoder.buffered(), 23);
+    assert_eq!(decoder.decode(&frame_a[23..]).len(), 1);
+    assert_eq!(decoder.stats().bytes_discarded, 0);
+}
+
+#[test]
+fn test_encode_known_frames() {
+    let frame_a = [85, 170, 22, 194, 0, 0, 0, 0, 0, 0, 255, 66, 240, 237, 0, 0, 0, 0, 0, 24, 90, 90, 90, 90];
+    let frame_b = [85, 170, 1, 22, 150, 186, 40, 129, 0, 0, 0, 45, 0x21, 2, 0, 1, 0, 0, 4, 24, 90, 90, 90, 90];
+    let sensor = TemperatureSensor::default();
+    assert_eq!(Frame::try_from(&frame_a).unwrap().to_bytes(sensor), frame_a);
+    assert_eq!(Frame::try_from(&frame_b).unwrap().to_bytes(sensor), frame_b);
+    let mpu6500 = Frame::parse(&frame_a, TemperatureSensor::Mpu6500).unwrap();
+    assert_eq!(mpu6500.to_bytes(TemperatureSensor::Mpu6500), frame_a);
+}
+
+#[test]
+fn test_encode_round_trip() {
+    use rand::Rng;
+    let mut rng = rand::thread_rng();
+    for _ in 0..10_000 {
+        let mut bytes: [u8; 24] = rng.gen();
+        bytes[0..2].copy_from_slice(&HEADER);
+        bytes[18] = [0x00, 0x04, 0x01, 0x03, 0x07][rng.gen_range(0..5)];
+        bytes[20..24].copy_from_slice(&FOOTER);
+        if bytes[18] == 0x03 {
+            let len = rng.gen_range(0..=12);
+            for (i, b) in bytes[2..14].iter_mut().enumerate() {
+                *b = if i < len {rng.gen_range(b'0'..=b'z')} else {0};
+            }
+        }
+
+        let sensor = [TemperatureSensor::Mpu6050, TemperatureSensor::Mpu6500][rng.gen_range(0..2)];
+        let frame = Frame::parse(&bytes, sensor).unwrap();
+        let encoded = frame.to_bytes(sensor);
+        let decoded = Frame::parse(&encoded, sensor).unwrap();
+        assert_eq!(decoded, frame);
+        assert_eq!(decoded.to_bytes(sensor), encoded);
+    }
+}
+
+#[test]
+fn test_light_mode_byte() {
+    let mut bytes = [0u8; FRAME_LEN];
+    bytes[0..2].copy_from_slice(&HEADER);
+    bytes[18] = 0x04;
+    bytes[20..24].copy_from_slice(&FOOTER);
+    bytes[14] = 9;
+    bytes[15] = 2;
+    assert!(matches!(Frame::try_from(&bytes), Ok(Frame::FrameB { light_mode: 2, .. })));
+}
+
+#[test]
+fn test_extended_frames() {
+    let pwm = [85, 170, 0x1A, 0x0A, 0x1F, 0x40, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 24, 90, 90, 90, 90];
+    assert_eq!(Frame::try_from(&pwm), Ok(Frame::FramePwm { pwm: 66.66, pwm_limit: 80.0 }));
+
+    let model = [85, 170, b'M', b'C', b'M', b'5', 0, 0, 0, 0, 0, 0, 0, 0, 0x08, 0x34, 0, 0, 3, 24, 90, 90, 90, 90];
+    assert_eq!(Frame::try_from(&model), Ok(Frame::FrameModel { model: "MCM5".into(), firmware: 2100 }));
+
+    let motor = [85, 170, 0xFF, 0x38, 0, 0, 0, 42, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 7, 24, 90, 90, 90, 90];
+    assert_eq!(Frame::try_from(&motor), Ok(Frame::FrameMotor {
+        battery_current: Current(-2.0),
+        motor_temperature: Temperature(42.0),
+    }));
+}
+
+#[test]
+fn test_settings_word() {
+    assert_eq!(Settings::from(0x2800), Settings {
+        pedals_mode: PedalMode::Medium,
+        speed_alarms: SpeedAlarmMode::Off,
+        roll_angle: RollAngle::Low,
+        in_miles: false,
+    });
+    assert_eq!(Settings::from(0x4481), Settings {
+        pedals_mode: PedalMode::Hard,
+        speed_alarms: SpeedAlarmMode::Second,
+        roll_angle: RollAngle::Medium,
+        in_miles: true,
+    });
+    assert_eq!(Settings::from(0x0000), Settings::default());
+    for word in [0x0000, 0x2800, 0x4481, 0x6D01, 0x0100] {
+        assert_eq!(u16::from(&Settings::from(word)), word);
+    }
+}
+
+#[test]
+fn test_temperature_sensor() {
+    let mut frame = [85, 170, 22, 194, 0, 0, 0, 0, 0, 0, 255, 66, 0, 0, 0, 0, 0, 0, 0, 24, 90, 90, 90, 90];
+    let temperature = |frame: &Frame| match frame {
+        Frame::FrameA { temperature, .. } => temperature.0,
+        _ => unreachable!(),
+    };
+
+    // 5000 reads 51.2C on an MPU6050 and 36C on an MPU6500
+    frame[12..14].copy_from_slice(&5000i16.to_be_bytes());
+    let mpu6050 = Frame::parse(&frame, TemperatureSensor::Mpu6050).unwrap();
+    let mpu6500 = Frame::parse(&frame, TemperatureSensor::Mpu6500).unwrap();
+    assert!((temperature(&mpu6050) - 51.236).abs() < 1e-2);
+    assert!((temperature(&mpu6500) - 35.976).abs() < 1e-2);
+
+    // 10000 is only plausible as an MPU6500 reading (65.9C vs 50.9C)
+    let mut decoder = FrameDecoder::with_sensor(TemperatureSensor::Auto);
+    frame[12..14].copy_from_slice(&10000i16.to_be_bytes());
+    for _ in 1..FrameDecoder::DETECT_READINGS {
+        decoder.decode(&frame);
+    }
+    assert_eq!(decoder.sensor(), TemperatureSensor::Auto);
+    // corrupt windows do not count
+    let mut garbage = frame;
+    garbage[21] = 0;
+    decoder.decode(&garbage);
+    assert_eq!(decoder.sensor(), TemperatureSensor::Auto);
+    decoder.decode(&frame);
+    assert_eq!(decoder.sensor(), TemperatureSensor::Mpu6500);
+    let hot = frame;
+    frame[12..14].copy_from_slice(&5000i16.to_be_bytes());
+    assert_eq!(decoder.decode(&frame), vec![mpu6500]);
+
+    // -14000 is only plausible as an MPU6050 reading, the count starts over
+    let mut cold = frame;
+    cold[12..14].copy_from_slice(&(-14000i16).to_be_bytes());
+    let mut decoder = FrameDecoder::with_sensor(TemperatureSensor::Auto);
+    let stream = [vec![hot; FrameDecoder::DETECT_READINGS - 1], vec![cold], vec![hot; FrameDecoder::DETECT_READINGS - 1]].concat();
+    decoder.decode(&stream.concat());
+    assert_eq!(decoder.sensor(), TemperatureSensor::Auto);
+
+    assert_eq!(TemperatureSensor::Mpu6500.raw(35.976), 5000);
+}
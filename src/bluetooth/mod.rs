@@ -1,11 +1,21 @@
 mod scan;
 mod device;
-mod frame;
+mod telemetry;
+pub mod transport;
+pub mod frame;
+pub mod simulator;
+pub mod capture;
+pub mod replay;
+pub mod command;
 
 pub use scan::*;
-pub use device::Device;
+pub use device::{Device, EucInfo, Field, BlueToothInfo};
+pub use telemetry::Telemetry;
+pub use transport::{WheelTransport, BleTransport, MemoryTransport};
+pub use simulator::{Simulator, WheelModel};
+pub use capture::{Recorder, CaptureReader, CaptureRecord, Direction};
+pub use replay::{ReplayTransport, ReplaySpeed};
+pub use command::{WheelCommand, CommandError};
 
 use btleplug::platform::{Peripheral};
-use btleplug::api::PeripheralProperties;
-use device::BlueToothInfo;
 use frame::Frame;
@@ -0,0 +1,162 @@
+use futures::future::BoxFuture;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::time::Instant;
+
+use super::capture::{CaptureReader, CaptureRecord, CaptureError, Direction};
+use super::device::BlueToothInfo;
+use super::transport::{WheelTransport, Notifications, TransportError};
+
+/// How fast a [`ReplayTransport`] plays its records.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaySpeed {
+    RealTime,
+    /// Time runs this many times faster than in the capture.
+    Accelerated(f32),
+    AsFastAsPossible,
+}
+
+impl ReplaySpeed {
+    /// When a record captured at `at` is due, counted from the replay start.
+    fn due(self, at: Duration) -> Option<Duration> {
+        match self {
+        ReplaySpeed::RealTime => Some(at),
+        ReplaySpeed::Accelerated(factor) if factor > 0.0 => Some(at.div_f32(factor)),
+        ReplaySpeed::Accelerated(_) | ReplaySpeed::AsFastAsPossible => None,
+        }
+    }
+}
+
+/// [`WheelTransport`] playing back the inbound chunks of a capture.
+///
+/// The chunks go through the same decoding path as a live wheel, so a
+/// recorded ride drives a [`super::Device`] and everything subscribed to it.
+/// Commands written to it are accepted and dropped; the link reports
+/// disconnected once the last chunk has been played.
+#[derive(Debug, Clone)]
+pub struct ReplayTransport {
+    name: String,
+    chunks: Arc<Vec<CaptureRecord>>,
+    speed: ReplaySpeed,
+    connected: Arc<AtomicBool>,
+}
+
+impl ReplayTransport {
+    pub fn new(name: &str, records: Vec<CaptureRecord>, speed: ReplaySpeed) -> Self {
+        let chunks = records.into_iter()
+            .filter(|record| record.direction == Direction::Inbound)
+            .collect();
+        Self {
+            name: name.to_owned(),
+            chunks: Arc::new(chunks),
+            speed,
+            connected: Arc::new(AtomicBool::new(true)),
+        }
+    }
+
+    /// Replays the capture file at `path`, named after the file.
+    pub fn open<P: AsRef<Path>>(path: P, speed: ReplaySpeed) -> Result<Self, CaptureError> {
+        let path = path.as_ref();
+        let records = CaptureReader::open(path)?.read_all()?;
+        let name = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        Ok(Self::new(&name, records, speed))
+    }
+
+    pub fn speed(&self) -> ReplaySpeed {
+        self.speed
+    }
+
+    /// Capture time of the last chunk, i.e. the length of the recording.
+    pub fn duration(&self) -> Duration {
+        self.chunks.last().map(|record| record.at).unwrap_or_default()
+    }
+}
+
+impl WheelTransport for ReplayTransport {
+    fn write<'a>(&'a self, _bytes: &'a [u8]) -> BoxFuture<'a, Result<(), TransportError>> {
+        Box::pin(async move { Ok(()) })
+    }
+
+    /// Each call replays the capture from its beginning.
+    fn notifications(&self) -> BoxFuture<'_, Result<Notifications, TransportError>> {
+        let replay = self.clone();
+        Box::pin(async move {
+            let start = Instant::now();
+            let offset = replay.chunks.first().map(|record| record.at).unwrap_or_default();
+            let stream = futures::stream::unfold(0, move |i| {
+                let replay = replay.clone();
+                async move {
+                    let record = match replay.chunks.get(i) {
+                    Some(record) => record,
+                    None => {
+                        replay.connected.store(false, Ordering::Relaxed);
+                        return None;
+                    }
+                    };
+                    match replay.speed.due(record.at.saturating_sub(offset)) {
+                    Some(due) => tokio::time::sleep_until(start + due).await,
+                    // still let the consumers run between chunks
+                    None => tokio::task::yield_now().await,
+                    }
+                    Some((record.bytes.clone(), i + 1))
+                }
+            });
+            Ok(Box::pin(stream) as Notifications)
+        })
+    }
+
+    fn info(&self) -> BoxFuture<'_, Result<BlueToothInfo, TransportError>> {
+        Box::pin(async move {
+            Ok(BlueToothInfo {
+                name: self.name.clone(),
+                is_connected: self.connected.load(Ordering::Relaxed),
+            })
+        })
+    }
+
+    fn disconnect(&self) -> BoxFuture<'_, Result<(), TransportError>> {
+        Box::pin(async move {
+            self.connected.store(false, Ordering::Relaxed);
+            Ok(())
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_replay() {
+    use crate::units::{Voltage, Distance};
+    use super::frame::{TEST_FRAME_A, TEST_FRAME_B};
+    let bytes = [TEST_FRAME_A, TEST_FRAME_B].concat();
+    let records: Vec<_> = bytes.chunks(20).enumerate()
+        .map(|(i, chunk)| CaptureRecord {
+            at: Duration::from_millis(1000 + 100 * i as u64),
+            direction: Direction::Inbound,
+            bytes: chunk.to_vec(),
+        })
+        .chain([CaptureRecord { at: Duration::from_millis(1050), direction: Direction::Outbound, bytes: b"b".to_vec() }])
+        .collect();
+
+    let replay = ReplayTransport::new("ride", records.clone(), ReplaySpeed::AsFastAsPossible);
+    assert_eq!(replay.duration(), Duration::from_millis(1200));
+    let mut device = super::Device::with_transport(replay).await.unwrap();
+    let mut info = device.subscribe();
+    while info.changed().await.is_ok() {
+        if info.borrow().updated(super::Field::TotalDistance).is_some() {
+            break;
+        }
+    }
+    device.update_info().await.unwrap();
+    assert_eq!(device.euc_info.voltage, Voltage(58.26));
+    assert_eq!(device.euc_info.total_distance, Distance(18257594.0));
+
+    let replay = ReplayTransport::new("ride", records, ReplaySpeed::Accelerated(4.0));
+    let start = std::time::Instant::now();
+    let stream = replay.notifications().await.unwrap();
+    let chunks: Vec<_> = futures::StreamExt::collect(stream).await;
+    // 200 ms of capture after the first chunk, played 4 times faster
+    assert_eq!(chunks.len(), 3);
+    assert!(start.elapsed() >= Duration::from_millis(50));
+    assert!(!replay.info().await.unwrap().is_connected);
+}
@@ -5,6 +5,7 @@ use btleplug::api::{Central, CharPropFlags, Manager as _, Peripheral, ScanFilter
 use btleplug::platform::Manager;
 use futures::stream::StreamExt;
 use std::error::Error;
+use crate::locale::{tr, Phrase};
 // use std::time::Duration;
 // use tokio::time;
 
@@ -14,7 +15,7 @@ pub async fn get_list_info() -> Result<Vec<super::BlueToothInfo>, Box<dyn Error>
     for p in f_list.into_iter() {
         lst.push( super::BlueToothInfo {
                 name: p.properties().await?.unwrap()
-                    .local_name.unwrap_or(String::from("(peripheral name unknown)")),
+                    .local_name.unwrap_or(String::from(tr(Phrase::UnknownName))),
                 is_connected: p.is_connected().await?,
         });
     }
@@ -25,12 +26,12 @@ pub async fn get_list() -> Result<Vec<super::Peripheral>, Box<dyn Error>> {
     let manager = Manager::new().await?;
     let adapter_list = manager.adapters().await?;
     if adapter_list.is_empty() {
-        eprintln!("No Bluetooth adapters found");
+        eprintln!("{}", tr(Phrase::NoAdapters));
     }
 
 //     let mut list = Vec::new();
     for adapter in adapter_list.iter() {
-        println!("Starting scan...");
+        println!("{}", tr(Phrase::StartingScan));
         adapter
             .start_scan(ScanFilter::default())
             .await
@@ -38,7 +39,7 @@ pub async fn get_list() -> Result<Vec<super::Peripheral>, Box<dyn Error>> {
 //         time::sleep(Duration::from_secs(2)).await;
         let peripherals = adapter.peripherals().await?;
 
-        println!("Найденные устройства: {}", peripherals.len());
+        println!("{}: {}", tr(Phrase::FoundDevices), peripherals.len());
         return Ok(peripherals);
     }
     Ok(Vec::new())
@@ -50,19 +51,18 @@ pub async fn connect(name: &str) -> Result<super::Peripheral, Box<dyn Error>> {
         .filter_map(|p| async{Some((p.properties().await.ok()?.unwrap().local_name?, p))})
 //         .find(|(pname, p)| pname==name).await?;
         .filter_map(|(pname, p)| async move {if pname == name {Some(p)} else {None}});
-    let peripheral = Box::pin(stream).next().await.ok_or("Устройство не найденно")?;
+    let peripheral = Box::pin(stream).next().await.ok_or(tr(Phrase::DeviceNotFound))?;
     let is_connected = peripheral.is_connected().await?;
     if !is_connected {
         // Connect if we aren't already connected.
-        println!("Подключаюсь");
+        println!("{}", tr(Phrase::Connecting));
         peripheral.connect().await?;
-        println!("Подключился");
+        println!("{}", tr(Phrase::Connected));
     }
     let is_connected = peripheral.is_connected().await?;
     if is_connected {
-        println!("Возвращаю Устройство");
         return Ok(peripheral);
     } else {
-        return Err("Устройство не подключается".into());
+        return Err(tr(Phrase::ConnectFailed).into());
     }
 }
@@ -0,0 +1,369 @@
+use futures::future::BoxFuture;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use super::device::BlueToothInfo;
+use super::frame::{Frame, Settings, Alerts, PedalMode, SpeedAlarmMode, RollAngle, TemperatureSensor};
+use super::transport::{WheelTransport, MemoryTransport, Notifications, TransportError};
+use crate::battery::BatteryConfig;
+use crate::units::{Speed, Distance, Voltage, Current, Temperature};
+
+/// Size of one HM-10 notification.
+const CHUNK: usize = 20;
+
+/// Simple physics model of a GotWay wheel and its rider.
+#[derive(Debug, Clone)]
+pub struct WheelModel {
+    pub target_speed: f32,  // km/h the rider is heading for
+    pub speed: f32,         // km/h
+    pub distance: f32,      // meters since power on
+    pub total_distance: f32,// meters
+    pub current: f32,       // amperes, negative while braking
+    pub temperature: f32,   // Celsius degrees
+    pub charge: f32,        // 0..1 of `capacity`
+    pub capacity: f32,      // Wh
+    pub battery: BatteryConfig,     // its `resistance` sags the voltage under load
+
+    pub settings: Settings,
+    pub tiltback_speed: Option<u8>, // km/h
+    pub led_mode: u8,
+    pub light_mode: u8,
+    pub beeps: u32,         // `b` commands received
+
+    pending: Vec<u8>,       // command bytes waiting for the rest of a sequence
+}
+
+impl Default for WheelModel {
+    fn default() -> Self {
+        Self {
+            target_speed: 0.0,
+            speed: 0.0,
+            distance: 0.0,
+            total_distance: 1_234_000.0,
+            current: 0.0,
+            temperature: 25.0,
+            charge: 0.9,
+            capacity: 1800.0,
+            battery: BatteryConfig::V84.with_resistance(0.15),
+            settings: Settings::default(),
+            tiltback_speed: Some(45),
+            led_mode: 0,
+            light_mode: 0,
+            beeps: 0,
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl WheelModel {
+    const AMBIENT: f32 = 25.0;
+    const MASS: f32 = 100.0;        // kg, wheel and rider
+    const MAX_ACCEL: f32 = 2.5;     // m/s^2
+    const ROLLING: f32 = 15.0;      // N
+    const DRAG: f32 = 0.3;          // N per (m/s)^2
+    const HEATING: f32 = 0.002;     // K/s per A^2
+    const COOLING: f32 = 0.01;      // 1/s towards ambient
+
+    /// Pack voltage at rest for the current charge, linear between 3.3V and 4.2V per cell.
+    pub fn rest_voltage(&self) -> f32 {
+        self.battery.cells as f32 * (3.3 + 0.9 * self.charge)
+    }
+
+    /// Pack voltage under the current load.
+    pub fn voltage(&self) -> f32 {
+        self.rest_voltage() - self.current * self.battery.resistance
+    }
+
+    /// Advances the model by `dt`.
+    pub fn step(&mut self, dt: Duration) {
+        let dt = dt.as_secs_f32();
+        let v = self.speed / 3.6;
+        let accel = ((self.target_speed / 3.6 - v) / dt.max(0.1))
+            .clamp(-Self::MAX_ACCEL, Self::MAX_ACCEL);
+        let force = Self::MASS * accel + Self::ROLLING * v.signum() + Self::DRAG * v * v.abs();
+        let power = force * v;
+
+        self.current = power / self.rest_voltage();
+        self.speed = (v + accel * dt) * 3.6;
+        let travelled = (v * dt).abs();
+        self.distance += travelled;
+        self.total_distance += travelled;
+        self.charge = (self.charge - power * dt / 3600.0 / self.capacity).clamp(0.0, 1.0);
+        self.temperature += (Self::HEATING * self.current * self.current
+            - Self::COOLING * (self.temperature - Self::AMBIENT)) * dt;
+    }
+
+    /// The FrameA/FrameB pair the wheel would send now.
+    pub fn frames(&self) -> (Frame, Frame) {
+        let a = Frame::FrameA {
+            voltage: self.battery.raw_voltage(Voltage(self.voltage())),
+            speed: Speed(self.speed),
+            distance: Distance(self.distance.round()),
+            current: Current(self.current),
+            temperature: Temperature(self.temperature),
+        };
+        let b = Frame::FrameB {
+            total_distance: Distance(self.total_distance.round()),
+            settings: self.settings.clone(),
+            tiltback_speed: self.tiltback_speed,
+            alerts: Alerts::default(),
+            led_mode: self.led_mode,
+            light_mode: self.light_mode,
+        };
+        (a, b)
+    }
+
+    /// Reacts to bytes written by the app. Sequences such as `W` `M` digit
+    /// may arrive split over several writes; a byte that does not continue
+    /// the sequence ends it and counts as a command of its own.
+    pub fn handle_command(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.pending.push(byte);
+            let sequence = self.pending.len() > 1;
+            if !self.apply_pending() && sequence {
+                self.pending = vec![byte];
+                self.apply_pending();
+            }
+        }
+    }
+
+    /// Acts on the pending bytes and clears them unless they start a longer
+    /// sequence; `false` when they are no command at all.
+    fn apply_pending(&mut self) -> bool {
+        let known = match self.pending.as_slice() {
+        [b'W'] | [b'W', b'M'] | [b'W', b'Y'] | [b'W', b'Y', b'0'..=b'9'] => return true,
+        [b'W', b'Y', tens @ b'0'..=b'9', units @ b'0'..=b'9'] => {
+            self.tiltback_speed = Some((tens - b'0') * 10 + units - b'0');
+            true
+        }
+        [b'"'] => {
+            self.tiltback_speed = None;
+            true
+        }
+        [b'W', b'M', digit @ b'0'..=b'9'] => {
+            self.led_mode = digit - b'0';
+            true
+        }
+        [b'b'] => {
+            self.beeps += 1;
+            true
+        }
+        [b'E'] => {
+            self.light_mode = 0;
+            true
+        }
+        [b'Q'] => {
+            self.light_mode = 1;
+            true
+        }
+        [b'T'] => {
+            self.light_mode = 2;
+            true
+        }
+        // `i` is both comfort pedals and second alarm only, the firmware decides
+        [mode @ (b'h' | b'f' | b's')] => {
+            self.settings.pedals_mode = match mode {
+            b'h' => PedalMode::Hard,
+            b'f' => PedalMode::Medium,
+            _ => PedalMode::Soft,
+            };
+            true
+        }
+        [alarms @ (b'u' | b'o')] => {
+            self.settings.speed_alarms = if *alarms == b'u' {SpeedAlarmMode::Both} else {SpeedAlarmMode::Off};
+            true
+        }
+        [angle @ (b'>' | b'=' | b'<')] => {
+            self.settings.roll_angle = match angle {
+            b'>' => RollAngle::Low,
+            b'=' => RollAngle::Medium,
+            _ => RollAngle::High,
+            };
+            true
+        }
+        [units @ (b'g' | b'm')] => {
+            self.settings.in_miles = *units == b'm';
+            true
+        }
+        _ => false,
+        };
+        self.pending.clear();
+        known
+    }
+}
+
+/// Virtual wheel usable as the transport of a [`super::Device`].
+///
+/// Every [`Simulator::tick`] advances the [`WheelModel`] and sends a FrameA
+/// and a FrameB split into 20-byte notifications, like the HM-10 module does.
+#[derive(Debug, Clone)]
+pub struct Simulator {
+    link: MemoryTransport,
+    model: Arc<Mutex<WheelModel>>,
+}
+
+impl Simulator {
+    pub fn new(name: &str, model: WheelModel) -> Self {
+        Self {
+            link: MemoryTransport::new(name),
+            model: Arc::new(Mutex::new(model)),
+        }
+    }
+
+    /// Copy of the current model state.
+    pub fn model(&self) -> WheelModel {
+        self.model.lock().unwrap().clone()
+    }
+
+    pub fn set_target_speed(&self, kmh: f32) {
+        self.model.lock().unwrap().target_speed = kmh;
+    }
+
+    /// Advances the model by `dt` and sends its frames.
+    pub fn tick(&self, dt: Duration) {
+        let (a, b) = {
+            let mut model = self.model.lock().unwrap();
+            model.step(dt);
+            model.frames()
+        };
+        let bytes = [a.to_bytes(TemperatureSensor::Mpu6050), b.to_bytes(TemperatureSensor::Mpu6050)].concat();
+        for chunk in bytes.chunks(CHUNK) {
+            self.link.notify(chunk);
+        }
+    }
+
+    /// Ticks every `period` on the tokio runtime until the handle is aborted.
+    pub fn run(&self, period: Duration) -> JoinHandle<()> {
+        let sim = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                sim.tick(period);
+            }
+        })
+    }
+
+    /// Number of open notification streams, i.e. connected telemetry tasks.
+    pub fn subscribers(&self) -> usize {
+        self.link.subscribers()
+    }
+
+    /// Waits for the first notification stream, see [`MemoryTransport::subscribed`].
+    pub async fn subscribed(&self) {
+        self.link.subscribed().await
+    }
+}
+
+impl WheelTransport for Simulator {
+    fn write<'a>(&'a self, bytes: &'a [u8]) -> BoxFuture<'a, Result<(), TransportError>> {
+        Box::pin(async move {
+            self.link.write(bytes).await?;
+            self.model.lock().unwrap().handle_command(bytes);
+            Ok(())
+        })
+    }
+
+    fn notifications(&self) -> BoxFuture<'_, Result<Notifications, TransportError>> {
+        self.link.notifications()
+    }
+
+    fn info(&self) -> BoxFuture<'_, Result<BlueToothInfo, TransportError>> {
+        self.link.info()
+    }
+
+    fn disconnect(&self) -> BoxFuture<'_, Result<(), TransportError>> {
+        self.link.disconnect()
+    }
+}
+
+#[test]
+fn test_model_physics() {
+    let mut model = WheelModel { target_speed: 30.0, ..Default::default() };
+    let rest = model.rest_voltage();
+    for _ in 0..50 {
+        model.step(Duration::from_millis(100));
+    }
+    assert!((model.speed - 30.0).abs() < 0.01);
+    assert!(model.current > 0.0);
+    assert!(model.voltage() < rest);
+    assert!(model.temperature > 25.0);
+    assert!(model.distance > 0.0);
+
+    model.handle_command(b"W");
+    model.handle_command(b"M");
+    model.handle_command(b"3");
+    model.handle_command(b"Qb");
+    assert_eq!((model.led_mode, model.light_mode, model.beeps), (3, 1, 1));
+    // a byte breaking a sequence is still a command
+    model.handle_command(b"W");
+    model.handle_command(b"bWYT");
+    assert_eq!((model.light_mode, model.beeps), (2, 2));
+    model.handle_command(b"WY07");
+    assert_eq!(model.tiltback_speed, Some(7));
+}
+
+#[tokio::test]
+async fn test_simulator_drives_device() {
+    let sim = Simulator::new("GotWay_Sim", WheelModel { target_speed: 20.0, ..Default::default() });
+    let mut device = super::Device::with_transport(sim.clone()).await.unwrap();
+    device.set_battery_config(BatteryConfig::V84);
+    let mut info = device.subscribe();
+    sim.subscribed().await;
+    sim.tick(Duration::from_millis(500));
+    info.changed().await.unwrap();
+    while info.borrow_and_update().updated(super::Field::TotalDistance).is_none() {
+        info.changed().await.unwrap();
+    }
+    device.update_info().await.unwrap();
+    let model = sim.model();
+    assert!((device.euc_info.speed.0 - model.speed).abs() < 0.05);
+    assert!((device.euc_info.voltage.0 - model.voltage()).abs() < 0.05);
+    assert_eq!(device.euc_info.total_distance, Distance(model.total_distance.round()));
+
+    device.set_led_mode(4).await.unwrap();
+    device.set_light_mode(2).await.unwrap();
+    let model = sim.model();
+    assert_eq!((model.led_mode, model.light_mode, model.beeps), (4, 2, 1));
+
+    use super::WheelCommand;
+    device.execute(&WheelCommand::PedalMode(PedalMode::Hard)).await.unwrap();
+    device.execute(&WheelCommand::RollAngle(RollAngle::Medium)).await.unwrap();
+    device.execute(&WheelCommand::Miles(true)).await.unwrap();
+    device.execute(&WheelCommand::SpeedAlarms(SpeedAlarmMode::Off)).await.unwrap();
+    assert!(device.execute(&WheelCommand::LightMode(7)).await.is_err());
+    let settings = sim.model().settings;
+    assert_eq!(settings, Settings {
+        pedals_mode: PedalMode::Hard,
+        speed_alarms: SpeedAlarmMode::Off,
+        roll_angle: RollAngle::Medium,
+        in_miles: true,
+    });
+    device.disconnect().await;
+}
+
+#[tokio::test]
+async fn test_tiltback_speed() {
+    use super::CommandError;
+    let sim = Simulator::new("GotWay_Sim", WheelModel::default());
+    let mut device = super::Device::with_transport(sim.clone()).await.unwrap();
+    sim.subscribed().await;
+    let ticks = sim.run(Duration::from_millis(50));
+    device.set_tiltback_speed(Some(35)).await.unwrap();
+    assert_eq!(sim.model().tiltback_speed, Some(35));
+    assert_eq!(device.euc_info.tiltback_speed, Some(35));
+    device.set_tiltback_speed(None).await.unwrap();
+    assert_eq!(sim.model().tiltback_speed, None);
+    assert!(matches!(device.set_tiltback_speed(Some(120)).await, Err(CommandError::InvalidTiltbackSpeed(120))));
+    assert_eq!(sim.model().tiltback_speed, None);
+    // a raw 0 km/h disables tiltback as well
+    let (_, b) = WheelModel { tiltback_speed: Some(0), ..Default::default() }.frames();
+    let bytes = b.to_bytes(TemperatureSensor::Mpu6050);
+    assert_eq!(bytes[10..12], [0, 0]);
+    assert!(matches!(Frame::try_from(&bytes), Ok(Frame::FrameB { tiltback_speed: None, .. })));
+    // nothing reports the new setting
+    ticks.abort();
+    assert!(matches!(device.set_tiltback_speed(Some(30)).await, Err(CommandError::NotConfirmed)));
+    device.disconnect().await;
+}
@@ -0,0 +1,168 @@
+use futures::StreamExt;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+use super::device::EucInfo;
+use super::transport::WheelTransport;
+use super::frame::{FrameDecoder, DecoderStats, TemperatureSensor};
+use super::capture::{Recorder, Direction};
+use crate::battery::BatteryConfig;
+
+/// Background task started once per connection.
+///
+/// It keeps a single notification stream of the transport, decodes the
+/// notifications continuously and publishes the updated [`EucInfo`] through a
+/// watch channel after every frame, so any number of consumers can follow the
+/// same stream. The task ends with [`Telemetry::stop`] or once the last
+/// handle is dropped.
+#[derive(Debug, Clone)]
+pub struct Telemetry {
+    info: watch::Receiver<EucInfo>,
+    shared: Arc<Mutex<Shared>>,
+    task: Arc<Task>,
+}
+
+/// Aborts the task when dropped.
+#[derive(Debug)]
+struct Task(JoinHandle<()>);
+
+impl Drop for Task {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// State the task shares with the [`Telemetry`] handles.
+#[derive(Debug, Default)]
+struct Shared {
+    decoder: FrameDecoder,
+    battery: BatteryConfig,
+    capture: Option<Recorder>,
+}
+
+impl Telemetry {
+    /// Spawns the decoding task for `transport` on the tokio runtime.
+    pub fn start<T: WheelTransport>(transport: T) -> Self {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let (tx, info) = watch::channel(EucInfo::default());
+        let task = tokio::spawn(Self::run(transport, shared.clone(), tx));
+        Self {
+            info, shared,
+            task: Arc::new(Task(task)),
+        }
+    }
+
+    async fn run<T: WheelTransport>(transport: T, shared: Arc<Mutex<Shared>>, tx: watch::Sender<EucInfo>) {
+        let mut stream = match transport.notifications().await {
+            Ok(stream) => stream,
+            Err(err) => {
+                eprintln!("Telemetry: no notifications: {}", err);
+                return;
+            }
+        };
+        while let Some(bytes) = stream.next().await {
+            // written outside the lock, the other handles don't wait for the disk
+            let capture = shared.lock().unwrap().capture.clone();
+            if let Some(capture) = capture {
+                if let Err(err) = capture.record(Direction::Inbound, &bytes) {
+                    eprintln!("Telemetry: capture failed: {}", err);
+                }
+            }
+            // rejected frames are counted in the decoder stats
+            let (frames, battery) = {
+                let mut shared = shared.lock().unwrap();
+                (shared.decoder.decode(&bytes), shared.battery.clone())
+            };
+            let now = Instant::now();
+            for frame in frames {
+                tx.send_modify(|info| {
+                    info.battery = battery.clone();
+                    info.set_frame(frame, now);
+                });
+            }
+        }
+    }
+
+    /// New receiver of the decoded info; `changed()` fails once the task has ended.
+    pub fn subscribe(&self) -> watch::Receiver<EucInfo> {
+        self.info.clone()
+    }
+
+    /// Last info published by the task.
+    pub fn latest(&self) -> EucInfo {
+        self.info.borrow().clone()
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.task.0.is_finished()
+    }
+
+    pub fn stop(&self) {
+        self.task.0.abort();
+    }
+
+    pub fn decoder_stats(&self) -> DecoderStats {
+        self.shared.lock().unwrap().decoder.stats().clone()
+    }
+
+    pub fn take_decoder_stats(&self) -> DecoderStats {
+        self.shared.lock().unwrap().decoder.take_stats()
+    }
+
+    pub fn temperature_sensor(&self) -> TemperatureSensor {
+        self.shared.lock().unwrap().decoder.sensor()
+    }
+
+    pub fn set_temperature_sensor(&self, sensor: TemperatureSensor) {
+        self.shared.lock().unwrap().decoder.set_sensor(sensor);
+    }
+
+    pub fn battery_config(&self) -> BatteryConfig {
+        self.shared.lock().unwrap().battery.clone()
+    }
+
+    /// Sets the pack the voltage is rescaled for, applied from the next frame.
+    pub fn set_battery_config(&self, battery: BatteryConfig) {
+        self.shared.lock().unwrap().battery = battery;
+    }
+
+    /// Records every inbound chunk to `capture` from now on, `None` to stop.
+    pub fn set_capture(&self, capture: Option<Recorder>) {
+        self.shared.lock().unwrap().capture = capture;
+    }
+}
+
+#[tokio::test]
+async fn test_stopped_on_drop() {
+    use super::transport::MemoryTransport;
+    let transport = MemoryTransport::new("GotWay_Test");
+    let telemetry = Telemetry::start(transport.clone());
+    let handle = telemetry.clone();
+    transport.subscribed().await;
+    drop(telemetry);
+    tokio::task::yield_now().await;
+    assert!(handle.is_running());
+    drop(handle);
+    while transport.subscribers() > 0 {
+        tokio::task::yield_now().await;
+    }
+}
+
+#[tokio::test]
+async fn test_rejected_frames_counted() {
+    use super::frame::TEST_FRAME_A;
+    use super::transport::MemoryTransport;
+    let transport = MemoryTransport::new("GotWay_Test");
+    let telemetry = Telemetry::start(transport.clone());
+    let mut info = telemetry.subscribe();
+    transport.subscribed().await;
+    let mut bad_footer = TEST_FRAME_A;
+    bad_footer[23] = 0;
+    transport.notify(&bad_footer);
+    transport.notify(&TEST_FRAME_A);
+    info.changed().await.unwrap();
+    let stats = telemetry.decoder_stats();
+    assert_eq!((stats.frames, stats.bad_footers), (1, 1));
+}
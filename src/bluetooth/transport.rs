@@ -0,0 +1,181 @@
+use super::Peripheral;
+use super::device::BlueToothInfo;
+use btleplug::api::{Characteristic, WriteType};
+use btleplug::api::Peripheral as _;
+use futures::future::BoxFuture;
+use futures::stream::{Stream, StreamExt};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+pub type TransportError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Inbound notification chunks, in the order the wheel sent them.
+pub type Notifications = Pin<Box<dyn Stream<Item = Vec<u8>> + Send>>;
+
+/// Link to the wheel below [`super::Device`].
+///
+/// The wheel side of the GotWay protocol is a plain byte pipe: ASCII
+/// commands go out, 24-byte frames come back split into notifications.
+pub trait WheelTransport: Clone + Send + Sync + 'static {
+    fn write<'a>(&'a self, bytes: &'a [u8]) -> BoxFuture<'a, Result<(), TransportError>>;
+    /// Starts a new stream of inbound notifications.
+    fn notifications(&self) -> BoxFuture<'_, Result<Notifications, TransportError>>;
+    fn info(&self) -> BoxFuture<'_, Result<BlueToothInfo, TransportError>>;
+    fn disconnect(&self) -> BoxFuture<'_, Result<(), TransportError>>;
+}
+
+const CHARACTERISTIC_UUID: Uuid = Uuid::from_u128(0x0000ffe1_0000_1000_8000_00805f9b34fb);
+
+/// [`WheelTransport`] over the HM-10 serial characteristic of a btleplug peripheral.
+#[derive(Debug, Clone)]
+pub struct BleTransport {
+    p: Peripheral,
+    char: Characteristic,
+}
+
+impl BleTransport {
+    pub async fn new(p: Peripheral) -> Result<Self, TransportError> {
+        p.discover_services().await?;
+        let chars = p.characteristics();
+        let char = chars.into_iter().find(|c| c.uuid == CHARACTERISTIC_UUID)
+            .ok_or("no FFE1 characteristic, not a GotWay wheel")?;
+        Ok(Self { p, char })
+    }
+}
+
+impl WheelTransport for BleTransport {
+    fn write<'a>(&'a self, bytes: &'a [u8]) -> BoxFuture<'a, Result<(), TransportError>> {
+        Box::pin(async move {
+            self.p.write(&self.char, bytes, WriteType::WithoutResponse).await?;
+            Ok(())
+        })
+    }
+
+    fn notifications(&self) -> BoxFuture<'_, Result<Notifications, TransportError>> {
+        Box::pin(async move {
+            self.p.subscribe(&self.char).await?;
+            let uuid = self.char.uuid;
+            let stream = self.p.notifications().await?
+                .filter_map(move |n| futures::future::ready((n.uuid == uuid).then_some(n.value)));
+            Ok(Box::pin(stream) as Notifications)
+        })
+    }
+
+    fn info(&self) -> BoxFuture<'_, Result<BlueToothInfo, TransportError>> {
+        Box::pin(async move {
+            let props = self.p.properties().await?.ok_or("no peripheral properties")?;
+            Ok(BlueToothInfo {
+                name: props.local_name.unwrap_or_default(),
+                is_connected: self.p.is_connected().await?,
+            })
+        })
+    }
+
+    fn disconnect(&self) -> BoxFuture<'_, Result<(), TransportError>> {
+        Box::pin(async move {
+            self.p.disconnect().await?;
+            Ok(())
+        })
+    }
+}
+
+/// In-memory [`WheelTransport`] for tests and simulations.
+///
+/// Bytes passed to [`MemoryTransport::notify`] reach every open notification
+/// stream; everything the device writes is kept in [`MemoryTransport::written`].
+#[derive(Debug, Clone)]
+pub struct MemoryTransport {
+    name: String,
+    inbound: broadcast::Sender<Vec<u8>>,
+    written: Arc<Mutex<Vec<Vec<u8>>>>,
+    connected: Arc<AtomicBool>,
+}
+
+impl MemoryTransport {
+    pub fn new(name: &str) -> Self {
+        let (inbound, _) = broadcast::channel(256);
+        Self {
+            name: name.to_owned(),
+            inbound,
+            written: Default::default(),
+            connected: Arc::new(AtomicBool::new(true)),
+        }
+    }
+
+    /// Sends one notification chunk to the device side.
+    pub fn notify(&self, bytes: &[u8]) {
+        if self.connected.load(Ordering::Relaxed) {
+            // no open stream is not an error, the chunk is simply lost as over the air
+            let _ = self.inbound.send(bytes.to_vec());
+        }
+    }
+
+    /// Number of notification streams currently open.
+    pub fn subscribers(&self) -> usize {
+        self.inbound.receiver_count()
+    }
+
+    /// Waits until a notification stream is open, e.g. once the telemetry
+    /// task of a new `Device` runs, so no chunk sent after it is lost.
+    pub async fn subscribed(&self) {
+        while self.subscribers() == 0 {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    /// Every write made so far, one entry per call.
+    pub fn written(&self) -> Vec<Vec<u8>> {
+        self.written.lock().unwrap().clone()
+    }
+
+    pub fn take_written(&self) -> Vec<Vec<u8>> {
+        std::mem::take(&mut *self.written.lock().unwrap())
+    }
+}
+
+impl WheelTransport for MemoryTransport {
+    fn write<'a>(&'a self, bytes: &'a [u8]) -> BoxFuture<'a, Result<(), TransportError>> {
+        Box::pin(async move {
+            if !self.connected.load(Ordering::Relaxed) {
+                return Err("not connected".into());
+            }
+            self.written.lock().unwrap().push(bytes.to_vec());
+            Ok(())
+        })
+    }
+
+    fn notifications(&self) -> BoxFuture<'_, Result<Notifications, TransportError>> {
+        let rx = self.inbound.subscribe();
+        Box::pin(async move {
+            let stream = futures::stream::unfold(rx, |mut rx| async move {
+                loop {
+                    match rx.recv().await {
+                    Ok(bytes) => return Some((bytes, rx)),
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                    }
+                }
+            });
+            Ok(Box::pin(stream) as Notifications)
+        })
+    }
+
+    fn info(&self) -> BoxFuture<'_, Result<BlueToothInfo, TransportError>> {
+        Box::pin(async move {
+            Ok(BlueToothInfo {
+                name: self.name.clone(),
+                is_connected: self.connected.load(Ordering::Relaxed),
+            })
+        })
+    }
+
+    fn disconnect(&self) -> BoxFuture<'_, Result<(), TransportError>> {
+        Box::pin(async move {
+            self.connected.store(false, Ordering::Relaxed);
+            Ok(())
+        })
+    }
+}
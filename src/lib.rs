@@ -1,3 +1,8 @@
-#![feature(array_chunks, slice_as_chunks)]
+#![feature(slice_as_chunks)]
 
 pub mod bluetooth;
+pub mod battery;
+pub mod units;
+pub mod locale;
+pub mod trip;
+pub mod alarm;
@@ -0,0 +1,150 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Language of the user facing strings, switchable at runtime with [`set_language`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Language {
+    #[default]
+    English,
+    Russian,
+}
+
+/// Every user facing string of the crate and its examples.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phrase {
+    WheelInfo,
+    Speed,
+    Distance,
+    TotalDistance,
+    Voltage,
+    Battery,
+    Current,
+    Temperature,
+
+    StartingScan,
+    NoAdapters,
+    FoundDevices,
+    UnknownName,
+    DeviceNotFound,
+    Connecting,
+    Connected,
+    NotConnected,
+    ConnectFailed,
+
+    Device,
+    Connect,
+    Disconnect,
+    Refresh,
+    Beep,
+    LedTurn,
+    LightTurn,
+    SwitchLanguage,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::Russian];
+
+    pub fn tr(self, phrase: Phrase) -> &'static str {
+        match self {
+        Language::English => english(phrase),
+        Language::Russian => russian(phrase),
+        }
+    }
+
+    /// The next language of [`Language::ALL`], for a toggle button.
+    pub fn next(self) -> Language {
+        let i = Self::ALL.iter().position(|&l| l == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+}
+
+static LANGUAGE: AtomicU8 = AtomicU8::new(0);
+
+pub fn language() -> Language {
+    Language::ALL.get(LANGUAGE.load(Ordering::Relaxed) as usize).copied().unwrap_or_default()
+}
+
+pub fn set_language(language: Language) {
+    let i = Language::ALL.iter().position(|&l| l == language).unwrap_or(0);
+    LANGUAGE.store(i as u8, Ordering::Relaxed);
+}
+
+/// `phrase` in the current language.
+pub fn tr(phrase: Phrase) -> &'static str {
+    language().tr(phrase)
+}
+
+fn english(phrase: Phrase) -> &'static str {
+    match phrase {
+    Phrase::WheelInfo => "Wheel info",
+    Phrase::Speed => "Speed",
+    Phrase::Distance => "Distance",
+    Phrase::TotalDistance => "Total distance",
+    Phrase::Voltage => "Voltage",
+    Phrase::Battery => "Battery",
+    Phrase::Current => "Current",
+    Phrase::Temperature => "Temperature",
+
+    Phrase::StartingScan => "Starting scan...",
+    Phrase::NoAdapters => "No Bluetooth adapters found",
+    Phrase::FoundDevices => "Devices found",
+    Phrase::UnknownName => "(peripheral name unknown)",
+    Phrase::DeviceNotFound => "Device not found",
+    Phrase::Connecting => "Connecting",
+    Phrase::Connected => "Connected",
+    Phrase::NotConnected => "Not connected",
+    Phrase::ConnectFailed => "Device does not connect",
+
+    Phrase::Device => "Device",
+    Phrase::Connect => "Connect",
+    Phrase::Disconnect => "Disconnect",
+    Phrase::Refresh => "Refresh",
+    Phrase::Beep => "Beep",
+    Phrase::LedTurn => "Led Turn",
+    Phrase::LightTurn => "Light Turn",
+    Phrase::SwitchLanguage => "Русский",
+    }
+}
+
+fn russian(phrase: Phrase) -> &'static str {
+    match phrase {
+    Phrase::WheelInfo => "Информация о колесе",
+    Phrase::Speed => "Скорость",
+    Phrase::Distance => "Дистанция",
+    Phrase::TotalDistance => "Пробег",
+    Phrase::Voltage => "Напряжение",
+    Phrase::Battery => "Заряд",
+    Phrase::Current => "Ток",
+    Phrase::Temperature => "Температура",
+
+    Phrase::StartingScan => "Начинаю поиск...",
+    Phrase::NoAdapters => "Bluetooth адаптеры не найдены",
+    Phrase::FoundDevices => "Найденные устройства",
+    Phrase::UnknownName => "(имя устройства неизвестно)",
+    Phrase::DeviceNotFound => "Устройство не найдено",
+    Phrase::Connecting => "Подключаюсь",
+    Phrase::Connected => "Подключено",
+    Phrase::NotConnected => "Не подключено",
+    Phrase::ConnectFailed => "Устройство не подключается",
+
+    Phrase::Device => "Устройство",
+    Phrase::Connect => "Подключиться",
+    Phrase::Disconnect => "Отключиться",
+    Phrase::Refresh => "Обновить",
+    Phrase::Beep => "Сигнал",
+    Phrase::LedTurn => "Подсветка",
+    Phrase::LightTurn => "Фара",
+    Phrase::SwitchLanguage => "English",
+    }
+}
+
+#[test]
+fn test_language_switch() {
+    assert_eq!(Language::Russian.tr(Phrase::Voltage), "Напряжение");
+    assert_eq!(Language::English.next(), Language::Russian);
+    assert_eq!(Language::Russian.next(), Language::English);
+
+    set_language(Language::Russian);
+    assert_eq!(tr(Phrase::Speed), "Скорость");
+    set_language(Language::English);
+    assert_eq!(tr(Phrase::Speed), "Speed");
+}
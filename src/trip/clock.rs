@@ -0,0 +1,60 @@
+use std::time::{Duration, Instant};
+
+use crate::bluetooth::{EucInfo, Field};
+
+/// Times successive [`EucInfo`]s from the arrival of one of their fields,
+/// as the `update` methods of the trip and alarm types expect: the first
+/// info is at zero, the next ones at their distance from it.
+#[derive(Debug, Clone)]
+pub struct SampleClock {
+    field: Field,
+    origin: Option<Instant>,
+}
+
+impl Default for SampleClock {
+    fn default() -> Self {
+        Self::of(Field::Speed)
+    }
+}
+
+impl SampleClock {
+    /// Clock following FrameA, the frame with the speed.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Clock following the frame that carries `field`.
+    pub fn of(field: Field) -> Self {
+        Self { field, origin: None }
+    }
+
+    /// Time of `info` when `field` last came in it, `None` if it never did.
+    pub fn at(&mut self, info: &EucInfo) -> Option<Duration> {
+        let received = info.updated(self.field)?;
+        let origin = *self.origin.get_or_insert(received);
+        Some(received.saturating_duration_since(origin))
+    }
+
+    /// Starts over at zero with the next info, e.g. after a reconnect.
+    pub fn reset(&mut self) {
+        self.origin = None;
+    }
+}
+
+#[test]
+fn test_sample_clock() {
+    let start = Instant::now();
+    let info = |field, ms| {
+        let mut info = EucInfo::default();
+        info.updated.insert(field, start + Duration::from_millis(ms));
+        info
+    };
+    let mut clock = SampleClock::new();
+    assert_eq!(clock.at(&EucInfo::default()), None);
+    assert_eq!(clock.at(&info(Field::Alerts, 0)), None);
+    assert_eq!(clock.at(&info(Field::Speed, 100)), Some(Duration::ZERO));
+    assert_eq!(clock.at(&info(Field::Speed, 350)), Some(Duration::from_millis(250)));
+    clock.reset();
+    assert_eq!(clock.at(&info(Field::Speed, 500)), Some(Duration::ZERO));
+    assert_eq!(SampleClock::of(Field::Alerts).at(&info(Field::Alerts, 0)), Some(Duration::ZERO));
+}
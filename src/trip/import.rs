@@ -0,0 +1,326 @@
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use crate::bluetooth::EucInfo;
+use crate::bluetooth::frame::{Alerts, PedalMode};
+use crate::units::{Speed, Distance, Voltage, Current, Temperature};
+
+/// One row of an imported log.
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub at: NaiveDateTime,  // local time of the phone that logged it
+    pub info: EucInfo,
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    Io(io::Error),
+    Empty,
+    /// The header has no column for this value.
+    MissingColumn(&'static str),
+    BadValue { line: usize, column: String, value: String },
+}
+
+impl std::fmt::Display for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+        ImportError::Io(err) => write!(f, "log i/o error: {}", err),
+        ImportError::Empty => write!(f, "log has no header"),
+        ImportError::MissingColumn(column) => write!(f, "log has no {} column", column),
+        ImportError::BadValue { line, column, value } => write!(f, "bad {} \"{}\" at line {}", column, value, line),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl From<io::Error> for ImportError {
+    fn from(err: io::Error) -> Self {
+        ImportError::Io(err)
+    }
+}
+
+/// Value a log column carries.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Column {
+    Date,
+    Time,
+    DateTime,
+    Speed,
+    Voltage,
+    Current,
+    Battery,
+    Distance,
+    TotalDistance,
+    Temperature,
+    Pwm,
+    Mode,
+    Alert,
+    Ignored,
+}
+
+/// Unit given in a column name, e.g. `speed (mph)` or `distance_km`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Unit {
+    Default,    // km/h, meters, Celsius degrees, as WheelLog logs them
+    Kmh,
+    Mph,
+    Meters,
+    Km,
+    Miles,
+    Celsius,
+    Fahrenheit,
+}
+
+impl Column {
+    fn from_header(name: &str) -> (Column, Unit) {
+        let name = name.trim().trim_matches('"').to_lowercase();
+        let (base, unit) = match name.find(['(', '[']) {
+        Some(i) => (name[..i].trim(), name[i + 1..].trim_end_matches([')', ']']).trim()),
+        None => match name.rsplit_once('_') {
+            Some((base, unit)) if Unit::from_name(unit) != Unit::Default => (base, unit),
+            _ => (name.as_str(), ""),
+            }
+        };
+        let column = match base.replace(' ', "_").as_str() {
+        "date" => Column::Date,
+        "time" => Column::Time,
+        "datetime" | "date_time" | "timestamp" => Column::DateTime,
+        "speed" | "wheel_speed" => Column::Speed,
+        "voltage" => Column::Voltage,
+        "current" => Column::Current,
+        "battery_level" | "battery" => Column::Battery,
+        "distance" | "trip_distance" => Column::Distance,
+        "totaldistance" | "total_distance" | "distance_total" => Column::TotalDistance,
+        "system_temp" | "temperature" | "temp" => Column::Temperature,
+        "pwm" => Column::Pwm,
+        "mode" => Column::Mode,
+        "alert" | "alerts" => Column::Alert,
+        _ => Column::Ignored,
+        };
+        (column, Unit::from_name(unit))
+    }
+}
+
+impl Unit {
+    fn from_name(name: &str) -> Unit {
+        match name {
+        "kmh" | "km/h" | "kph" => Unit::Kmh,
+        "mph" => Unit::Mph,
+        "m" => Unit::Meters,
+        "km" => Unit::Km,
+        "mi" | "miles" => Unit::Miles,
+        "c" | "°c" => Unit::Celsius,
+        "f" | "°f" => Unit::Fahrenheit,
+        _ => Unit::Default,
+        }
+    }
+}
+
+/// Reads the CSV logs of the Android WheelLog and EUC World apps, including
+/// the ones written by [`super::TripLogger`].
+///
+/// Columns are found by their header name in any order, unknown ones are
+/// skipped, and units given in the header (`speed (mph)`, `distance_km`...)
+/// are converted. Fields without a column keep their default value.
+#[derive(Debug)]
+pub struct LogReader<R: BufRead> {
+    input: R,
+    separator: char,
+    columns: Vec<(Column, Unit, String)>,
+    line: usize,
+}
+
+impl LogReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, ImportError> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: BufRead> LogReader<R> {
+    pub fn new(mut input: R) -> Result<Self, ImportError> {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Err(ImportError::Empty);
+        }
+        let header = header.trim_start_matches('\u{feff}').trim_end();
+        let separator = if header.contains(';') && !header.contains(',') {';'} else {','};
+        let columns: Vec<_> = header.split(separator)
+            .map(|name| {
+                let (column, unit) = Column::from_header(name);
+                (column, unit, name.trim().to_owned())
+            })
+            .collect();
+        let has = |column| columns.iter().any(|(c, _, _)| *c == column);
+        if !has(Column::DateTime) && !has(Column::Time) {
+            return Err(ImportError::MissingColumn("time"));
+        }
+        if !has(Column::Speed) {
+            return Err(ImportError::MissingColumn("speed"));
+        }
+        Ok(Self { input, separator, columns, line: 1 })
+    }
+
+    pub fn read_all(self) -> Result<Vec<Sample>, ImportError> {
+        self.collect()
+    }
+
+    fn parse(&self, line: &str) -> Result<Sample, ImportError> {
+        let mut info = EucInfo::default();
+        let mut date = None;
+        let mut time = None;
+        let mut datetime = None;
+        for ((column, unit, name), value) in self.columns.iter().zip(line.split(self.separator)) {
+            let value = value.trim().trim_matches('"');
+            let bad = || ImportError::BadValue { line: self.line, column: name.clone(), value: value.to_owned() };
+            let number = || value.parse::<f32>().map_err(|_| bad());
+            if value.is_empty() || *column == Column::Ignored {
+                continue;
+            }
+            match column {
+            Column::Date => date = Some(parse_date(value).ok_or_else(bad)?),
+            Column::Time => time = Some(NaiveTime::parse_from_str(value, "%H:%M:%S%.f").map_err(|_| bad())?),
+            Column::DateTime => datetime = Some(parse_datetime(value).ok_or_else(bad)?),
+            Column::Speed => info.speed = match unit {
+                Unit::Mph => Speed::from_mph(number()?),
+                _ => Speed(number()?),
+                },
+            Column::Voltage => info.voltage = Voltage(number()?),
+            Column::Current => info.current = Current(number()?),
+            Column::Battery => info.battery_percent = number()?,
+            Column::Distance => info.distance = distance(number()?, *unit),
+            Column::TotalDistance => info.total_distance = distance(number()?, *unit),
+            Column::Temperature => info.temperature = match unit {
+                Unit::Fahrenheit => Temperature::from_fahrenheit(number()?),
+                _ => Temperature(number()?),
+                },
+            Column::Pwm => info.pwm = number()?,
+            Column::Mode => info.settings.pedals_mode = parse_mode(value).ok_or_else(bad)?,
+            Column::Alert => info.alerts = parse_alerts(value).ok_or_else(bad)?,
+            Column::Ignored => (),
+            }
+        }
+        let at = match (datetime, date, time) {
+        (Some(at), _, _) => at,
+        (None, Some(date), Some(time)) => date.and_time(time),
+        // the day of time only logs is unknown, they are put on 1970-01-01
+        (None, None, Some(time)) => NaiveDate::default().and_time(time),
+        (None, _, None) => return Err(ImportError::MissingColumn("time")),
+        };
+        Ok(Sample { at, info })
+    }
+}
+
+impl<R: BufRead> Iterator for LogReader<R> {
+    type Item = Result<Sample, ImportError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let mut line = String::new();
+            match self.input.read_line(&mut line) {
+            Ok(0) => return None,
+            Ok(_) => (),
+            Err(err) => return Some(Err(err.into())),
+            }
+            self.line += 1;
+            if !line.trim().is_empty() {
+                return Some(self.parse(line.trim_end()));
+            }
+        }
+    }
+}
+
+fn distance(value: f32, unit: Unit) -> Distance {
+    match unit {
+    Unit::Km => Distance::from_km(value),
+    Unit::Miles => Distance::from_miles(value),
+    _ => Distance(value),
+    }
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    ["%Y-%m-%d", "%Y/%m/%d", "%d.%m.%Y"].iter()
+        .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+}
+
+/// Text date and time, or a Unix timestamp in seconds or milliseconds
+/// converted to local time like the text ones.
+fn parse_datetime(value: &str) -> Option<NaiveDateTime> {
+    if let Ok(stamp) = value.parse::<i64>() {
+        let millis = if stamp > 100_000_000_000 {stamp} else {stamp * 1000};
+        return Local.timestamp_millis_opt(millis).single().map(|at| at.naive_local());
+    }
+    ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f", "%Y/%m/%d %H:%M:%S%.f", "%d.%m.%Y %H:%M:%S%.f"].iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value.trim_end_matches('Z'), format).ok())
+}
+
+fn parse_mode(value: &str) -> Option<PedalMode> {
+    match value.to_lowercase().as_str() {
+    "hard" | "0" => Some(PedalMode::Hard),
+    "medium" | "1" => Some(PedalMode::Medium),
+    "soft" | "2" => Some(PedalMode::Soft),
+    "comfort" | "3" => Some(PedalMode::Comfort),
+    _ => None,
+    }
+}
+
+/// Space separated alert names, as [`Alerts`] displays them.
+fn parse_alerts(value: &str) -> Option<Alerts> {
+    value.split_whitespace()
+        .map(|name| (0..8).map(|bit| 1u8 << bit)
+            .find(|&mask| Alerts::from(mask).to_string().eq_ignore_ascii_case(name)))
+        .try_fold(0u8, |bits, mask| mask.map(|mask| bits | mask))
+        .map(Alerts::from)
+}
+
+#[test]
+fn test_import_logs() {
+    use chrono::Utc;
+    use super::TripLogger;
+    let at = Local.with_ymd_and_hms(2022, 10, 12, 18, 30, 1).unwrap();
+    let mut info = EucInfo {
+        speed: Speed(25.5),
+        voltage: Voltage(80.0),
+        current: Current(-1.5),
+        battery_percent: 87.0,
+        distance: Distance(1234.0),
+        total_distance: Distance(1_234_567.0),
+        temperature: Temperature(37.0),
+        alerts: Alerts::from(0x06),
+        ..Default::default()
+    };
+    info.settings.pedals_mode = PedalMode::Medium;
+    let text = format!("{}\n{}\n\n", TripLogger::HEADER, TripLogger::row(&info, at, None));
+    let samples = LogReader::new(text.as_bytes()).unwrap().read_all().unwrap();
+    assert_eq!(samples.len(), 1);
+    assert_eq!(samples[0].at, at.naive_local());
+    let imported = &samples[0].info;
+    assert_eq!((imported.speed, imported.voltage, imported.current), (info.speed, info.voltage, info.current));
+    assert_eq!((imported.distance, imported.total_distance), (info.distance, info.total_distance));
+    assert_eq!((imported.temperature, imported.battery_percent), (info.temperature, info.battery_percent));
+    assert_eq!((imported.settings.pedals_mode, &imported.alerts), (PedalMode::Medium, &info.alerts));
+
+    // other app: reordered columns, units in the header, unknown columns
+    let text = "\u{feff}datetime;latitude;Speed (mph);Distance_km;Temperature [F];voltage;total_distance_mi\n\
+        2022-10-12 18:30:01.500;55.75;10;1.5;98.6;80.5;100\n";
+    let samples = LogReader::new(text.as_bytes()).unwrap().read_all().unwrap();
+    let imported = &samples[0].info;
+    assert_eq!(samples[0].at, NaiveDate::from_ymd_opt(2022, 10, 12).unwrap().and_hms_milli_opt(18, 30, 1, 500).unwrap());
+    assert_eq!(imported.speed, Speed::from_mph(10.0));
+    assert_eq!(imported.distance, Distance(1500.0));
+    assert_eq!(imported.total_distance, Distance::from_miles(100.0));
+    assert!((imported.temperature.celsius() - 37.0).abs() < 0.01);
+    assert_eq!(imported.voltage, Voltage(80.5));
+
+    let text = "date,time,speed\n2022-10-12,18:30:01.000,fast\n";
+    let mut reader = LogReader::new(text.as_bytes()).unwrap();
+    assert!(matches!(reader.next(), Some(Err(ImportError::BadValue { line: 2, .. }))));
+    let samples = LogReader::new("time,speed,alert\n18:30:01.000,0,Speed1 Speed1 TransportMode TransportMode\n".as_bytes()).unwrap().read_all().unwrap();
+    assert_eq!(samples[0].info.alerts, Alerts::from(0x84));
+    let samples = LogReader::new("timestamp,speed\n1665599401500,0\n".as_bytes()).unwrap().read_all().unwrap();
+    let utc = Utc.with_ymd_and_hms(2022, 10, 12, 18, 30, 1).unwrap() + chrono::Duration::milliseconds(500);
+    assert_eq!(samples[0].at, utc.with_timezone(&Local).naive_local());
+    assert!(matches!(LogReader::new("date,voltage\n".as_bytes()), Err(ImportError::MissingColumn("time"))));
+}
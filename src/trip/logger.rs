@@ -0,0 +1,255 @@
+use chrono::{DateTime, Local};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tokio::sync::watch;
+
+use crate::bluetooth::{EucInfo, Field};
+use super::{TripSegmenter, TripEvent, TripId};
+
+/// Writes [`EucInfo`] updates to one WheelLog style CSV file per trip.
+///
+/// Values are always metric, like in WheelLog. Every row is flushed right
+/// away, so the file is complete up to the last update even when the
+/// connection or the program dies.
+#[derive(Debug)]
+pub struct TripLogger {
+    dir: PathBuf,
+    trip: Option<(PathBuf, BufWriter<File>)>,
+    starting: Vec<(EucInfo, DateTime<Local>)>,     // samples of a trip not confirmed yet
+}
+
+impl TripLogger {
+    /// Header of the Android WheelLog app's CSV logs, minus the GPS and
+    /// extra sensor columns this crate has no data for, plus the trip id.
+    pub const HEADER: &'static str = "date,time,speed,voltage,current,power,battery_level,distance,totaldistance,system_temp,mode,alert,trip";
+
+    /// Logger creating its trip files in `dir`.
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Self { dir: dir.as_ref().to_owned(), trip: None, starting: Vec::new() }
+    }
+
+    /// File name of a trip started at `at`, as WheelLog names them.
+    pub fn file_name(at: DateTime<Local>) -> String {
+        at.format("%Y_%m_%d_%H_%M_%S.csv").to_string()
+    }
+
+    /// File of the trip in progress.
+    pub fn path(&self) -> Option<&Path> {
+        self.trip.as_ref().map(|(path, _)| path.as_path())
+    }
+
+    /// Ends the current trip, if any, and starts a new file. A trip started
+    /// within the same second as an existing file gets a `_2`, `_3`... suffix.
+    pub fn start_trip(&mut self, at: DateTime<Local>) -> io::Result<&Path> {
+        self.end_trip()?;
+        fs::create_dir_all(&self.dir)?;
+        let name = Self::file_name(at);
+        let mut path = self.dir.join(&name);
+        for n in 2.. {
+            if !path.exists() {
+                break;
+            }
+            path = self.dir.join(name.replace(".csv", &format!("_{}.csv", n)));
+        }
+        let mut out = BufWriter::new(File::create(&path)?);
+        writeln!(out, "{}", Self::HEADER)?;
+        out.flush()?;
+        Ok(&self.trip.insert((path, out)).0)
+    }
+
+    /// Closes the current trip file and returns its path.
+    pub fn end_trip(&mut self) -> io::Result<Option<PathBuf>> {
+        match self.trip.take() {
+        Some((path, mut out)) => {
+            out.flush()?;
+            Ok(Some(path))
+        }
+        None => Ok(None),
+        }
+    }
+
+    /// Appends one row of `trip`, starting a file first if none is open.
+    pub fn log(&mut self, info: &EucInfo, at: DateTime<Local>, trip: Option<TripId>) -> io::Result<()> {
+        if self.trip.is_none() {
+            self.start_trip(at)?;
+        }
+        let (_, out) = self.trip.as_mut().unwrap();
+        writeln!(out, "{}", Self::row(info, at, trip))?;
+        out.flush()
+    }
+
+    /// One CSV row in the WheelLog number formats, the trip column is empty
+    /// for samples outside trips.
+    pub fn row(info: &EucInfo, at: DateTime<Local>, trip: Option<TripId>) -> String {
+        format!("{},{},{:.2},{:.2},{:.2},{:.2},{:.0},{:.0},{:.0},{:.0},{:?},{},{}",
+            at.format("%Y-%m-%d"),
+            at.format("%H:%M:%S%.3f"),
+            info.speed.kmh(),
+            info.voltage.0,
+            info.current.0,
+            info.power().0,
+            info.battery_percent,
+            info.distance.meters(),
+            info.total_distance.meters(),
+            info.temperature.celsius(),
+            info.settings.pedals_mode,
+            info.alerts,
+            trip.map_or(String::new(), |trip| trip.0.to_string()),
+        )
+    }
+
+    /// Logs `info`, taken `at`, if `segmenter` puts it in a trip: every trip
+    /// gets its own file, from the sample that started it on, and samples
+    /// outside trips are not logged. Returns the files of the trips that ended.
+    pub fn push(&mut self, segmenter: &mut TripSegmenter, info: &EucInfo, at: DateTime<Local>) -> io::Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        for event in segmenter.push(info) {
+            match event {
+            TripEvent::Started { trip, .. } => {
+                files.extend(self.end_trip()?);
+                let starting = std::mem::take(&mut self.starting);
+                self.start_trip(starting.first().map_or(at, |(_, at)| *at))?;
+                for (info, at) in &starting {
+                    self.log(info, *at, Some(trip))?;
+                }
+            }
+            TripEvent::Ended { .. } => files.extend(self.end_trip()?),
+            TripEvent::Paused { .. } | TripEvent::Resumed { .. } => (),
+            }
+        }
+        if let Some(trip) = segmenter.trip() {
+            self.log(info, at, Some(trip))?;
+        }
+        if segmenter.is_starting() {
+            self.starting.push((info.clone(), at));
+        } else {
+            self.starting.clear();
+        }
+        Ok(files)
+    }
+
+    /// Logs the infos published on `info` trip by trip, see [`Self::push`],
+    /// stamped with the arrival of their FrameA. Returns the trip files once
+    /// the telemetry task ends.
+    pub async fn run(mut self, mut info: watch::Receiver<EucInfo>, mut segmenter: TripSegmenter) -> io::Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        let base = (Instant::now(), Local::now());
+        while info.changed().await.is_ok() {
+            let sample = info.borrow_and_update().clone();
+            if let Some(received) = sample.updated(Field::Speed) {
+                files.extend(self.push(&mut segmenter, &sample, Self::wall_time(base, received))?);
+            }
+        }
+        segmenter.disconnect();
+        files.extend(self.end_trip()?);
+        Ok(files)
+    }
+
+    /// `instant` on the wall clock, from a `base` read from both clocks at once.
+    fn wall_time(base: (Instant, DateTime<Local>), instant: Instant) -> DateTime<Local> {
+        let (base_instant, base_time) = base;
+        let offset = |duration| chrono::Duration::from_std(duration).unwrap_or_else(|_| chrono::Duration::zero());
+        match instant.checked_duration_since(base_instant) {
+        Some(after) => base_time + offset(after),
+        None => base_time - offset(base_instant - instant),
+        }
+    }
+}
+
+impl Drop for TripLogger {
+    fn drop(&mut self) {
+        let _ = self.end_trip();
+    }
+}
+
+#[test]
+fn test_trip_logger() {
+    use chrono::TimeZone;
+    use crate::bluetooth::frame::{Alerts, PedalMode};
+    use crate::units::{Speed, Voltage, Current, Distance, Temperature};
+    let dir = std::env::temp_dir().join(format!("gotway_trips_{}", std::process::id()));
+    let start = Local.with_ymd_and_hms(2022, 10, 12, 18, 30, 1).unwrap();
+    let mut info = EucInfo {
+        speed: Speed(25.5),
+        voltage: Voltage(80.0),
+        current: Current(-1.5),
+        battery_percent: 87.4,
+        distance: Distance(1234.0),
+        total_distance: Distance(1_234_567.0),
+        temperature: Temperature(36.6),
+        alerts: Alerts::from(0x06),
+        ..Default::default()
+    };
+    info.settings.pedals_mode = PedalMode::Medium;
+
+    let mut logger = TripLogger::new(&dir);
+    logger.log(&info, start, Some(TripId(3))).unwrap();
+    let path = logger.path().unwrap().to_owned();
+    assert_eq!(path, dir.join("2022_10_12_18_30_01.csv"));
+    // flushed without closing the trip
+    let text = fs::read_to_string(&path).unwrap();
+    assert_eq!(text, format!("{}\n2022-10-12,18:30:01.000,25.50,80.00,-1.50,-120.00,87,1234,1234567,37,Medium,Speed2 Speed1,3\n", TripLogger::HEADER));
+
+    let later = start + chrono::Duration::milliseconds(250);
+    logger.log(&EucInfo::default(), later, None).unwrap();
+    assert_eq!(logger.end_trip().unwrap(), Some(path.clone()));
+    let text = fs::read_to_string(&path).unwrap();
+    assert!(text.ends_with("2022-10-12,18:30:01.250,0.00,0.00,0.00,0.00,0,0,0,0,Soft,,\n"));
+    assert_eq!(logger.end_trip().unwrap(), None);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_push_splits_trips() {
+    use chrono::TimeZone;
+    use std::time::Duration;
+    use crate::units::{Speed, Distance};
+    let dir = std::env::temp_dir().join(format!("gotway_push_trips_{}", std::process::id()));
+    let start = Instant::now();
+    let wall = Local.with_ymd_and_hms(2022, 10, 12, 18, 30, 0).unwrap();
+    let mut segmenter = TripSegmenter::new();
+    segmenter.idle_timeout = Duration::from_secs(20);
+    let mut logger = TripLogger::new(&dir);
+    let mut push = |secs, speed, distance| {
+        let mut info = EucInfo { speed: Speed(speed), distance: Distance(distance), ..Default::default() };
+        info.updated.insert(Field::Speed, start + Duration::from_secs(secs));
+        logger.push(&mut segmenter, &info, wall + chrono::Duration::seconds(secs as i64)).unwrap()
+    };
+    let rows = |path: &Path| fs::read_to_string(path).unwrap().lines().skip(1)
+        .map(|row| { let cols = row.split(',').collect::<Vec<_>>(); format!("{} {}", cols[1], cols[12]) })
+        .collect::<Vec<_>>();
+
+    // standing, then starting: not in a trip yet
+    assert!(push(0, 0.0, 0.0).is_empty());
+    assert!(push(1, 10.0, 0.0).is_empty());
+    assert!(!dir.exists());
+    // confirmed by the counter, the trip starts with the sample that started it
+    push(2, 10.0, 5.0);
+    push(3, 0.0, 5.0);
+    push(13, 0.0, 5.0);
+    // idle timeout: the trip ends, its file is complete
+    let first = push(40, 0.0, 5.0);
+    assert_eq!(first, vec![dir.join("2022_10_12_18_30_01.csv")]);
+    assert_eq!(rows(&first[0]), vec!["18:30:01.000 1", "18:30:02.000 1", "18:30:03.000 1", "18:30:13.000 1"]);
+    // a start that is not confirmed leaves nothing behind
+    push(41, 10.0, 5.0);
+    push(42, 0.0, 5.0);
+    push(43, 10.0, 5.0);
+    push(44, 10.0, 10.0);
+    assert_eq!(logger.end_trip().unwrap(), Some(dir.join("2022_10_12_18_30_43.csv")));
+    assert_eq!(rows(&dir.join("2022_10_12_18_30_43.csv")), vec!["18:30:43.000 2", "18:30:44.000 2"]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_wall_time() {
+    use chrono::TimeZone;
+    use std::time::Duration;
+    let now = Instant::now() + Duration::from_secs(10);
+    let base = (now, Local.with_ymd_and_hms(2022, 10, 12, 18, 30, 0).unwrap());
+    let time = |instant| TripLogger::wall_time(base, instant).format("%H:%M:%S%.3f").to_string();
+    assert_eq!(time(now + Duration::from_millis(1250)), "18:30:01.250");
+    assert_eq!(time(now - Duration::from_millis(250)), "18:29:59.750");
+}
@@ -0,0 +1,11 @@
+mod logger;
+mod import;
+mod stats;
+mod segment;
+mod clock;
+
+pub use logger::TripLogger;
+pub use import::{LogReader, Sample, ImportError};
+pub use stats::TripStats;
+pub use segment::{TripSegmenter, TripEvent, TripId, EndReason};
+pub use clock::SampleClock;
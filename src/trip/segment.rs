@@ -0,0 +1,230 @@
+use std::time::Duration;
+
+use crate::bluetooth::EucInfo;
+use crate::units::Distance;
+use super::SampleClock;
+
+/// Sequence number of a trip found by [`TripSegmenter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TripId(pub u32);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EndReason {
+    /// Standing still for [`TripSegmenter::idle_timeout`].
+    Idle,
+    Disconnected,
+    /// The odometer moved further than seen while paused: the wheel was
+    /// ridden without us, that is another trip.
+    OdometerJump,
+}
+
+/// Trip lifecycle, with `at` on the same clock as the samples.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TripEvent {
+    Started { trip: TripId, at: Duration },
+    Paused { trip: TripId, at: Duration },
+    Resumed { trip: TripId, at: Duration },
+    Ended { trip: TripId, at: Duration, reason: EndReason },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum State {
+    Idle,
+    /// Fast enough, waiting for the wheel counters to confirm it.
+    Starting { at: Duration, distance: Distance, total_distance: Distance },
+    Riding { trip: TripId, stopped: Option<Duration> },
+    Paused { trip: TripId, since: Duration, total_distance: Distance },
+}
+
+/// Splits the telemetry stream into trips.
+///
+/// A trip starts when the speed goes over `start_speed` and the wheel's own
+/// trip `distance` or `total_distance` counter confirms the wheel moves, so
+/// a speed glitch on a standing wheel starts nothing. Standing still for
+/// `pause_after` pauses it, for `idle_timeout` ends it, as does a disconnect.
+#[derive(Debug, Clone)]
+pub struct TripSegmenter {
+    pub start_speed: f32,           // km/h
+    pub pause_after: Duration,
+    pub idle_timeout: Duration,
+    /// Most `total_distance` may grow while paused before it is a new trip.
+    pub max_paused_distance: Distance,
+
+    state: State,
+    next_id: u32,
+    last: Option<Duration>,     // time of the last sample
+    clock: SampleClock,
+}
+
+impl Default for TripSegmenter {
+    fn default() -> Self {
+        Self {
+            start_speed: 3.0,
+            pause_after: Duration::from_secs(10),
+            idle_timeout: Duration::from_secs(300),
+            max_paused_distance: Distance(50.0),
+            state: State::Idle,
+            next_id: 1,
+            last: None,
+            clock: SampleClock::new(),
+        }
+    }
+}
+
+impl TripSegmenter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Numbers trips from `id` on, e.g. to continue after the last logged trip.
+    pub fn with_next_id(self, id: u32) -> Self {
+        Self { next_id: id, ..self }
+    }
+
+    /// Trip the last sample belongs to, paused ones included.
+    pub fn trip(&self) -> Option<TripId> {
+        match self.state {
+        State::Riding { trip, .. } | State::Paused { trip, .. } => Some(trip),
+        State::Idle | State::Starting { .. } => None,
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        matches!(self.state, State::Paused { .. })
+    }
+
+    /// Fast enough for a trip, the counters have not confirmed it yet.
+    pub fn is_starting(&self) -> bool {
+        matches!(self.state, State::Starting { .. })
+    }
+
+    /// Adds the latest telemetry info, timed by the [`SampleClock`] of its FrameAs.
+    pub fn push(&mut self, info: &EucInfo) -> Vec<TripEvent> {
+        match self.clock.at(info) {
+        Some(at) => self.update(info, at),
+        None => Vec::new(),
+        }
+    }
+
+    /// Adds a sample taken `at` from any fixed origin.
+    pub fn update(&mut self, info: &EucInfo, at: Duration) -> Vec<TripEvent> {
+        let mut events = Vec::new();
+        let moving = info.speed.kmh().abs() >= self.start_speed;
+        self.state = match std::mem::replace(&mut self.state, State::Idle) {
+        State::Idle if moving => State::Starting { at, distance: info.distance, total_distance: info.total_distance },
+        State::Idle => State::Idle,
+        State::Starting { .. } if !moving => State::Idle,
+        State::Starting { at: started, distance, total_distance } => {
+            // a restarted wheel counts its trip distance from zero again
+            let counted = info.distance.0 > distance.0 || info.total_distance.0 > total_distance.0;
+            if counted {
+                let trip = self.new_trip();
+                events.push(TripEvent::Started { trip, at: started });
+                State::Riding { trip, stopped: None }
+            } else {
+                State::Starting { at: started, distance, total_distance }
+            }
+        }
+        State::Riding { trip, .. } if moving => State::Riding { trip, stopped: None },
+        State::Riding { trip, stopped } => {
+            let since = stopped.unwrap_or(at);
+            if at.saturating_sub(since) >= self.pause_after {
+                events.push(TripEvent::Paused { trip, at: since });
+                State::Paused { trip, since, total_distance: info.total_distance }
+            } else {
+                State::Riding { trip, stopped: Some(since) }
+            }
+        }
+        State::Paused { trip, since, total_distance } => {
+            if info.total_distance.0 - total_distance.0 > self.max_paused_distance.0 {
+                events.push(TripEvent::Ended { trip, at: since, reason: EndReason::OdometerJump });
+                if moving {
+                    let trip = self.new_trip();
+                    events.push(TripEvent::Started { trip, at });
+                    State::Riding { trip, stopped: None }
+                } else {
+                    State::Idle
+                }
+            } else if moving {
+                events.push(TripEvent::Resumed { trip, at });
+                State::Riding { trip, stopped: None }
+            } else if at.saturating_sub(since) >= self.idle_timeout {
+                events.push(TripEvent::Ended { trip, at: since, reason: EndReason::Idle });
+                State::Idle
+            } else {
+                State::Paused { trip, since, total_distance }
+            }
+        }
+        };
+        self.last = Some(at);
+        events
+    }
+
+    /// The link is gone: ends the trip in progress at the last sample.
+    pub fn disconnect(&mut self) -> Option<TripEvent> {
+        let at = self.last.unwrap_or_default();
+        let event = match self.state {
+        State::Riding { trip, stopped } => Some(TripEvent::Ended { trip, at: stopped.unwrap_or(at), reason: EndReason::Disconnected }),
+        State::Paused { trip, since, .. } => Some(TripEvent::Ended { trip, at: since, reason: EndReason::Disconnected }),
+        State::Idle | State::Starting { .. } => None,
+        };
+        self.state = State::Idle;
+        self.clock.reset();
+        self.last = None;
+        event
+    }
+
+    fn new_trip(&mut self) -> TripId {
+        let trip = TripId(self.next_id);
+        self.next_id += 1;
+        trip
+    }
+}
+
+#[test]
+fn test_trip_segments() {
+    use crate::units::Speed;
+    let sample = |speed, distance, total_distance| EucInfo {
+        speed: Speed(speed),
+        distance: Distance(distance),
+        total_distance: Distance(total_distance),
+        ..Default::default()
+    };
+    let secs = Duration::from_secs;
+    let mut segmenter = TripSegmenter::new();
+
+    // speed glitch on a standing wheel
+    assert!(segmenter.update(&sample(20.0, 0.0, 1000.0), secs(0)).is_empty());
+    assert!(segmenter.update(&sample(0.0, 0.0, 1000.0), secs(1)).is_empty());
+    assert_eq!(segmenter.trip(), None);
+
+    segmenter.update(&sample(10.0, 0.0, 1000.0), secs(2));
+    assert!(segmenter.is_starting());
+    assert_eq!(segmenter.update(&sample(10.0, 3.0, 1003.0), secs(3)),
+        vec![TripEvent::Started { trip: TripId(1), at: secs(2) }]);
+    assert!(segmenter.update(&sample(0.0, 100.0, 1100.0), secs(20)).is_empty());
+    assert_eq!(segmenter.update(&sample(0.0, 100.0, 1100.0), secs(30)),
+        vec![TripEvent::Paused { trip: TripId(1), at: secs(20) }]);
+    assert!(segmenter.is_paused());
+    // wheel restarted during the pause, same trip
+    assert_eq!(segmenter.update(&sample(10.0, 0.0, 1100.0), secs(40)),
+        vec![TripEvent::Resumed { trip: TripId(1), at: secs(40) }]);
+    segmenter.update(&sample(0.0, 50.0, 1150.0), secs(50));
+    segmenter.update(&sample(0.0, 50.0, 1150.0), secs(60));
+    assert_eq!(segmenter.update(&sample(0.0, 50.0, 1150.0), secs(350)),
+        vec![TripEvent::Ended { trip: TripId(1), at: secs(50), reason: EndReason::Idle }]);
+    assert_eq!(segmenter.trip(), None);
+
+    segmenter.update(&sample(10.0, 50.0, 1150.0), secs(400));
+    segmenter.update(&sample(10.0, 60.0, 1160.0), secs(401));
+    segmenter.update(&sample(0.0, 60.0, 1160.0), secs(402));
+    segmenter.update(&sample(0.0, 60.0, 1160.0), secs(412));
+    // reconnected after a ride we did not see
+    assert_eq!(segmenter.update(&sample(15.0, 2000.0, 3160.0), secs(500)), vec![
+        TripEvent::Ended { trip: TripId(2), at: secs(402), reason: EndReason::OdometerJump },
+        TripEvent::Started { trip: TripId(3), at: secs(500) },
+    ]);
+    assert_eq!(segmenter.disconnect(),
+        Some(TripEvent::Ended { trip: TripId(3), at: secs(500), reason: EndReason::Disconnected }));
+    assert_eq!(segmenter.disconnect(), None);
+}
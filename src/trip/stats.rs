@@ -0,0 +1,174 @@
+use std::time::Duration;
+
+use crate::bluetooth::EucInfo;
+use crate::units::{Speed, Distance, Voltage, Current, Power, Temperature};
+use super::{Sample, SampleClock};
+
+/// Running statistics of a trip, fed one [`EucInfo`] at a time.
+///
+/// Distance comes from the wheel's own trip counter, so it does not depend
+/// on how often samples arrive; a counter going backwards (the wheel was
+/// switched off and on) continues from zero instead of losing the trip.
+/// Time and energy are integrated between samples, except over gaps longer
+/// than [`TripStats::MAX_GAP`], e.g. while disconnected.
+#[derive(Debug, Clone, Default)]
+pub struct TripStats {
+    pub ride_time: Duration,        // time with telemetry
+    pub moving_time: Duration,      // time above `MOVING_SPEED`
+    pub distance: Distance,
+    pub max_speed: Speed,
+    pub max_current: Current,
+    pub max_power: Power,
+    pub max_temperature: Option<Temperature>,
+    pub min_voltage: Option<Voltage>,
+    pub energy_drawn: f32,          // Wh taken from the battery
+    pub energy_regenerated: f32,    // Wh put back while braking
+
+    clock: SampleClock,
+    last: Option<Last>,
+}
+
+/// What integration needs from the previous sample.
+#[derive(Debug, Clone)]
+struct Last {
+    at: Duration,
+    speed: Speed,
+    power: Power,
+    distance: Distance,
+}
+
+impl TripStats {
+    /// Slower than this the wheel is standing, km/h.
+    pub const MOVING_SPEED: f32 = 2.0;
+    /// Longer gaps between samples are not integrated.
+    pub const MAX_GAP: Duration = Duration::from_secs(5);
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Statistics of an imported log.
+    pub fn from_samples(samples: &[Sample]) -> Self {
+        let mut stats = Self::new();
+        if let Some(first) = samples.first() {
+            for sample in samples {
+                let at = (sample.at - first.at).to_std().unwrap_or_default();
+                stats.update(&sample.info, at);
+            }
+        }
+        stats
+    }
+
+    /// Adds the latest telemetry info, timed by its last FrameA. Infos
+    /// without a new FrameA are ignored, so it can be called on every update
+    /// of [`crate::bluetooth::Device::subscribe`].
+    pub fn push(&mut self, info: &EucInfo) {
+        if let Some(at) = self.clock.at(info) {
+            self.update(info, at);
+        }
+    }
+
+    /// Adds a sample taken `at` from any fixed origin; samples not newer than
+    /// the previous one are ignored.
+    pub fn update(&mut self, info: &EucInfo, at: Duration) {
+        let power = info.power();
+        if let Some(last) = &self.last {
+            if at <= last.at {
+                return;
+            }
+            let dt = at - last.at;
+            if dt <= Self::MAX_GAP {
+                self.ride_time += dt;
+                if last.speed.kmh().abs() >= Self::MOVING_SPEED {
+                    self.moving_time += dt;
+                }
+                let energy = last.power.0 * dt.as_secs_f32() / 3600.0;
+                if energy >= 0.0 {
+                    self.energy_drawn += energy;
+                } else {
+                    self.energy_regenerated -= energy;
+                }
+            }
+            self.distance.0 += if info.distance.0 >= last.distance.0 {
+                info.distance.0 - last.distance.0
+            } else {
+                info.distance.0
+            };
+        }
+
+        self.max_speed = Speed(self.max_speed.0.max(info.speed.0.abs()));
+        self.max_current = Current(self.max_current.0.max(info.current.0));
+        self.max_power = Power(self.max_power.0.max(power.0));
+        self.max_temperature = Some(self.max_temperature.map_or(info.temperature, |t| Temperature(t.0.max(info.temperature.0))));
+        self.min_voltage = Some(self.min_voltage.map_or(info.voltage, |v| Voltage(v.0.min(info.voltage.0))));
+        self.last = Some(Last { at, speed: info.speed, power, distance: info.distance });
+    }
+
+    /// Average speed while moving.
+    pub fn avg_speed(&self) -> Speed {
+        Self::speed(self.distance, self.moving_time)
+    }
+
+    /// Average speed over the whole ride time, stops included.
+    pub fn avg_ride_speed(&self) -> Speed {
+        Self::speed(self.distance, self.ride_time)
+    }
+
+    fn speed(distance: Distance, time: Duration) -> Speed {
+        if time.is_zero() {
+            return Speed(0.0);
+        }
+        Speed(distance.km() / (time.as_secs_f32() / 3600.0))
+    }
+
+    /// Energy drawn minus energy regenerated, Wh.
+    pub fn energy_used(&self) -> f32 {
+        self.energy_drawn - self.energy_regenerated
+    }
+
+    /// Net consumption, `None` before the first 100 meters.
+    pub fn wh_per_km(&self) -> Option<f32> {
+        (self.distance.0 >= 100.0).then(|| self.energy_used() / self.distance.km())
+    }
+}
+
+#[test]
+fn test_trip_stats() {
+    let sample = |speed, distance, voltage, current, temperature| EucInfo {
+        speed: Speed(speed),
+        distance: Distance(distance),
+        voltage: Voltage(voltage),
+        current: Current(current),
+        temperature: Temperature(temperature),
+        ..Default::default()
+    };
+    let mut stats = TripStats::new();
+    stats.update(&sample(0.0, 0.0, 84.0, 0.0, 30.0), Duration::from_secs(0));
+    // 360 s at 36 km/h drawing 800 W
+    for t in 1..=361 {
+        let temperature = if t == 200 {40.0} else {35.0};
+        stats.update(&sample(36.0, 10.0 * (t - 1) as f32, 80.0, 10.0, temperature), Duration::from_secs(t));
+    }
+    // the same sample twice, as after a FrameB
+    stats.update(&sample(36.0, 3600.0, 80.0, 10.0, 40.0), Duration::from_secs(361));
+    // braking for 5 s, regenerating 410 W
+    stats.update(&sample(0.0, 3650.0, 82.0, -5.0, 38.0), Duration::from_secs(362));
+    stats.update(&sample(0.0, 3650.0, 84.0, 0.0, 38.0), Duration::from_secs(367));
+    // wheel restarted, then a long gap
+    stats.update(&sample(20.0, 100.0, 84.0, 5.0, 30.0), Duration::from_secs(368));
+    stats.update(&sample(20.0, 200.0, 84.0, 5.0, 30.0), Duration::from_secs(400));
+
+    assert_eq!(stats.ride_time, Duration::from_secs(368));
+    assert_eq!(stats.moving_time, Duration::from_secs(361));
+    assert_eq!(stats.distance, Distance(3850.0));
+    assert_eq!(stats.max_speed, Speed(36.0));
+    assert_eq!(stats.max_current, Current(10.0));
+    assert_eq!(stats.max_power, Power(800.0));
+    assert_eq!(stats.max_temperature, Some(Temperature(40.0)));
+    assert_eq!(stats.min_voltage, Some(Voltage(80.0)));
+    assert!((stats.energy_drawn - (80.0 + 800.0 / 3600.0)).abs() < 0.01);
+    assert!((stats.energy_regenerated - 410.0 / 3600.0 * 5.0).abs() < 0.01);
+    assert!((stats.wh_per_km().unwrap() - stats.energy_used() / 3.85).abs() < 0.001);
+    assert!((stats.avg_speed().kmh() - 3.85 / (361.0 / 3600.0)).abs() < 0.01);
+    assert_eq!(TripStats::new().wh_per_km(), None);
+}
@@ -0,0 +1,196 @@
+use std::fmt;
+
+/// Units quantities are shown in.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum UnitSystem {
+    #[default]
+    Metric,     // km/h, km, m, Celsius
+    Imperial,   // mph, mi, ft, Fahrenheit
+}
+
+impl UnitSystem {
+    /// Units the wheel itself is set to, from the miles flag of its settings word.
+    pub fn from_wheel(settings: &crate::bluetooth::frame::Settings) -> Self {
+        if settings.in_miles {UnitSystem::Imperial} else {UnitSystem::Metric}
+    }
+}
+
+const METERS_PER_MILE: f32 = 1609.344;
+const METERS_PER_FOOT: f32 = 0.3048;
+
+/// Speed, km/h.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct Speed(pub f32);
+
+/// Distance, meters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct Distance(pub f32);
+
+/// Voltage, volts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct Voltage(pub f32);
+
+/// Current, amperes. Negative while regenerating.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct Current(pub f32);
+
+/// Power, watts. Negative while regenerating.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct Power(pub f32);
+
+/// Temperature, Celsius degrees.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct Temperature(pub f32);
+
+impl Speed {
+    pub fn from_mph(mph: f32) -> Self {
+        Self(mph * METERS_PER_MILE / 1000.0)
+    }
+    pub fn kmh(self) -> f32 {
+        self.0
+    }
+    pub fn mph(self) -> f32 {
+        self.0 * 1000.0 / METERS_PER_MILE
+    }
+}
+
+impl Distance {
+    pub fn from_km(km: f32) -> Self {
+        Self(km * 1000.0)
+    }
+    pub fn from_miles(miles: f32) -> Self {
+        Self(miles * METERS_PER_MILE)
+    }
+    pub fn meters(self) -> f32 {
+        self.0
+    }
+    pub fn km(self) -> f32 {
+        self.0 / 1000.0
+    }
+    pub fn miles(self) -> f32 {
+        self.0 / METERS_PER_MILE
+    }
+    pub fn feet(self) -> f32 {
+        self.0 / METERS_PER_FOOT
+    }
+}
+
+impl Temperature {
+    pub fn from_fahrenheit(f: f32) -> Self {
+        Self((f - 32.0) * 5.0 / 9.0)
+    }
+    pub fn celsius(self) -> f32 {
+        self.0
+    }
+    pub fn fahrenheit(self) -> f32 {
+        self.0 * 9.0 / 5.0 + 32.0
+    }
+}
+
+impl std::ops::Mul<Current> for Voltage {
+    type Output = Power;
+    fn mul(self, current: Current) -> Power {
+        Power(self.0 * current.0)
+    }
+}
+
+/// Quantity that can be written out in either unit system.
+///
+/// The precision of the formatter (`{:.3}`) overrides the default number of decimals.
+pub trait Quantity: Copy {
+    fn format(&self, units: UnitSystem, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+
+    fn display(self, units: UnitSystem) -> Display<Self> {
+        Display(self, units)
+    }
+}
+
+/// [`Quantity`] bound to a unit system, see [`Quantity::display`].
+#[derive(Debug, Clone, Copy)]
+pub struct Display<Q>(Q, UnitSystem);
+
+impl<Q: Quantity> fmt::Display for Display<Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.format(self.1, f)
+    }
+}
+
+fn write_value(f: &mut fmt::Formatter<'_>, value: f32, decimals: usize, unit: &str) -> fmt::Result {
+    write!(f, "{:.*} {}", f.precision().unwrap_or(decimals), value, unit)
+}
+
+impl Quantity for Speed {
+    fn format(&self, units: UnitSystem, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match units {
+        UnitSystem::Metric => write_value(f, self.kmh(), 1, "km/h"),
+        UnitSystem::Imperial => write_value(f, self.mph(), 1, "mph"),
+        }
+    }
+}
+
+impl Quantity for Distance {
+    fn format(&self, units: UnitSystem, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match units {
+        UnitSystem::Metric if self.0.abs() < 1000.0 => write_value(f, self.meters(), 0, "m"),
+        UnitSystem::Metric => write_value(f, self.km(), 2, "km"),
+        UnitSystem::Imperial if self.miles().abs() < 0.1 => write_value(f, self.feet(), 0, "ft"),
+        UnitSystem::Imperial => write_value(f, self.miles(), 2, "mi"),
+        }
+    }
+}
+
+impl Quantity for Voltage {
+    fn format(&self, _units: UnitSystem, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_value(f, self.0, 2, "V")
+    }
+}
+
+impl Quantity for Current {
+    fn format(&self, _units: UnitSystem, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_value(f, self.0, 2, "A")
+    }
+}
+
+impl Quantity for Power {
+    fn format(&self, _units: UnitSystem, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_value(f, self.0, 0, "W")
+    }
+}
+
+impl Quantity for Temperature {
+    fn format(&self, units: UnitSystem, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match units {
+        UnitSystem::Metric => write_value(f, self.celsius(), 1, "°C"),
+        UnitSystem::Imperial => write_value(f, self.fahrenheit(), 1, "°F"),
+        }
+    }
+}
+
+macro_rules! display_metric {
+    ($($ty:ty),*) => {$(
+        impl fmt::Display for $ty {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.format(UnitSystem::Metric, f)
+            }
+        }
+    )*};
+}
+display_metric!(Speed, Distance, Voltage, Current, Power, Temperature);
+
+#[test]
+fn test_units_format() {
+    assert_eq!(Distance(0.0).to_string(), "0 m");
+    assert_eq!(Distance(999.0).to_string(), "999 m");
+    assert_eq!(Distance(1234.5).to_string(), "1.23 km");
+    assert_eq!(Distance(18257594.0).to_string(), "18257.59 km");
+    assert_eq!(Distance(12_345_678_912.0).display(UnitSystem::Metric).to_string(), "12345679.00 km");
+    assert_eq!(Distance(100.0).display(UnitSystem::Imperial).to_string(), "328 ft");
+    assert_eq!(Distance::from_miles(2.5).display(UnitSystem::Imperial).to_string(), "2.50 mi");
+
+    assert_eq!(Speed(36.0).to_string(), "36.0 km/h");
+    assert_eq!(Speed::from_mph(30.0).display(UnitSystem::Imperial).to_string(), "30.0 mph");
+    assert_eq!(format!("{:.3}", Voltage(58.26)), "58.260 V");
+    assert_eq!((Voltage(100.0) * Current(-2.5)).to_string(), "-250 W");
+    assert_eq!(Temperature(25.0).display(UnitSystem::Imperial).to_string(), "77.0 °F");
+    assert_eq!(Temperature::from_fahrenheit(77.0), Temperature(25.0));
+}